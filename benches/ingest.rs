@@ -0,0 +1,112 @@
+//! Compare the throughput of the bag types when counting a
+//! large text.
+//!
+//! Run with `cargo bench`. The text is made of random words
+//! drawn from a fixed vocabulary, so results are repeatable.
+
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bbow::{Bbow, HashBbow};
+use rustc_hash::FxBuildHasher;
+
+/// Size of the generated text, in bytes.
+const TEXT_SIZE: usize = 16 << 20;
+
+/// Number of distinct words in the generated text.
+const VOCABULARY_SIZE: usize = 20_000;
+
+/// Times each measurement is repeated: the fastest is kept.
+const RUNS: usize = 3;
+
+/// A text of about `TEXT_SIZE` bytes of punctuated words.
+fn text() -> String {
+    // A small linear congruential generator is plenty here.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize
+    };
+    let vocabulary: Vec<String> = (0..VOCABULARY_SIZE)
+        .map(|_| {
+            let length = 2 + next() % 9;
+            (0..length)
+                .map(|_| (b'a' + (next() % 26) as u8) as char)
+                .collect()
+        })
+        .collect();
+    let mut text = String::with_capacity(TEXT_SIZE + 16);
+    while text.len() < TEXT_SIZE {
+        // Skew toward common words, as in natural text.
+        let rank = next() % VOCABULARY_SIZE;
+        let word = &vocabulary[rank * rank / VOCABULARY_SIZE];
+        if next() % 10 == 0 {
+            text.push_str(&word.to_uppercase());
+        } else {
+            text.push_str(word);
+        }
+        text.push_str(match next() % 12 {
+            0 => ", ",
+            1 => ". ",
+            _ => " ",
+        });
+    }
+    text
+}
+
+/// Run `f` `RUNS` times and report the fastest throughput.
+fn measure<T>(name: &str, text: &str, mut f: impl FnMut() -> T) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(f());
+        best = best.min(start.elapsed());
+    }
+    let megabytes = text.len() as f64 / (1 << 20) as f64;
+    println!(
+        "{:<28} {:>8.1} ms {:>8.1} MiB/s",
+        name,
+        best.as_secs_f64() * 1e3,
+        megabytes / best.as_secs_f64()
+    );
+}
+
+fn main() {
+    let text = text();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    println!("{} MiB of text", text.len() >> 20);
+
+    measure("Bbow", &text, || Bbow::new().extend_from_text(&text).len());
+    measure("HashBbow", &text, || {
+        HashBbow::new().extend_from_text(&text).len()
+    });
+    measure("HashBbow, FxHash", &text, || {
+        HashBbow::with_hasher(FxBuildHasher)
+            .extend_from_text(&text)
+            .len()
+    });
+    measure("HashBbow into Bbow", &text, || {
+        Bbow::from(HashBbow::new().extend_from_text(&text)).len()
+    });
+    measure(&format!("Bbow, {} threads", threads), &text, || {
+        Bbow::new().par_extend_from_text(&text, threads).len()
+    });
+    measure("Bbow bigrams", &text, || {
+        Bbow::new().with_ngrams(1..=2).extend_from_text(&text).len()
+    });
+    measure("HashBbow bigrams", &text, || {
+        HashBbow::new()
+            .with_ngrams(1..=2)
+            .extend_from_text(&text)
+            .len()
+    });
+    measure("HashBbow bigrams, FxHash", &text, || {
+        HashBbow::with_hasher(FxBuildHasher)
+            .with_ngrams(1..=2)
+            .extend_from_text(&text)
+            .len()
+    });
+}
@@ -0,0 +1,284 @@
+//! The logic shared by [Bbow] and [HashBbow].
+//!
+//! Both kinds of bag count words into a map from words to
+//! counts, and keep their settings and surface forms in a
+//! [Bbow]: a [Bbow] in itself, a [HashBbow] in a template.
+//! They differ only in the kind of map. The [WordMap] and
+//! [Bag] traits cover what they have in common, so that
+//! counting, combining and ranking words are written once,
+//! here, for both.
+
+use std::borrow::Cow;
+use std::cmp::{self, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque};
+use std::hash::BuildHasher;
+
+use crate::{for_each_ngram, Bbow, StopWords, SurfaceForms};
+
+/// A map from words to their counts.
+pub(crate) trait WordMap<'a>: IntoIterator<Item = (Cow<'a, str>, usize)> {
+    /// The count of `word`, added as 0 if it is missing.
+    fn count_mut(&mut self, word: Cow<'a, str>) -> &mut usize;
+
+    /// The count of `word`, if it is present.
+    fn get(&self, word: &str) -> Option<usize>;
+
+    /// Keep only the words for which `keep`, which may change
+    /// their count, returns `true`.
+    fn retain<F: FnMut(&str, &mut usize) -> bool>(&mut self, keep: F);
+
+    /// The words with their counts.
+    fn pairs<'s>(&'s self) -> impl Iterator<Item = (&'s Cow<'a, str>, usize)>
+    where
+        'a: 's;
+}
+
+impl<'a> WordMap<'a> for BTreeMap<Cow<'a, str>, usize> {
+    fn count_mut(&mut self, word: Cow<'a, str>) -> &mut usize {
+        self.entry(word).or_insert(0)
+    }
+
+    fn get(&self, word: &str) -> Option<usize> {
+        BTreeMap::get(self, word).copied()
+    }
+
+    fn retain<F: FnMut(&str, &mut usize) -> bool>(&mut self, mut keep: F) {
+        BTreeMap::retain(self, |word, count| keep(word, count));
+    }
+
+    fn pairs<'s>(&'s self) -> impl Iterator<Item = (&'s Cow<'a, str>, usize)>
+    where
+        'a: 's,
+    {
+        self.iter().map(|(word, &count)| (word, count))
+    }
+}
+
+impl<'a, S: BuildHasher> WordMap<'a> for HashMap<Cow<'a, str>, usize, S> {
+    fn count_mut(&mut self, word: Cow<'a, str>) -> &mut usize {
+        self.entry(word).or_insert(0)
+    }
+
+    fn get(&self, word: &str) -> Option<usize> {
+        HashMap::get(self, word).copied()
+    }
+
+    fn retain<F: FnMut(&str, &mut usize) -> bool>(&mut self, mut keep: F) {
+        HashMap::retain(self, |word, count| keep(word, count));
+    }
+
+    fn pairs<'s>(&'s self) -> impl Iterator<Item = (&'s Cow<'a, str>, usize)>
+    where
+        'a: 's,
+    {
+        self.iter().map(|(word, &count)| (word, count))
+    }
+}
+
+/// A bag of words: a [WordMap] of counts, and the [Bbow]
+/// holding the settings used to count them and the surface
+/// forms recorded.
+pub(crate) trait Bag<'a> {
+    type Map: WordMap<'a>;
+
+    fn counts(&self) -> &Self::Map;
+
+    fn counts_mut(&mut self) -> &mut Self::Map;
+
+    fn settings(&self) -> &Bbow<'a>;
+
+    fn settings_mut(&mut self) -> &mut Bbow<'a>;
+
+    /// Split the bag into its counts and surface forms.
+    fn into_parts(self) -> (Self::Map, Option<SurfaceForms<'a>>);
+
+    /// Add one occurrence of `key`.
+    fn insert(&mut self, key: Cow<'a, str>) {
+        *self.counts_mut().count_mut(key) += 1;
+    }
+}
+
+impl<'a> Bag<'a> for Bbow<'a> {
+    type Map = BTreeMap<Cow<'a, str>, usize>;
+
+    fn counts(&self) -> &Self::Map {
+        &self.words
+    }
+
+    fn counts_mut(&mut self) -> &mut Self::Map {
+        &mut self.words
+    }
+
+    fn settings(&self) -> &Bbow<'a> {
+        self
+    }
+
+    fn settings_mut(&mut self) -> &mut Bbow<'a> {
+        self
+    }
+
+    fn into_parts(self) -> (Self::Map, Option<SurfaceForms<'a>>) {
+        (self.words, self.surface_forms)
+    }
+
+    fn insert(&mut self, key: Cow<'a, str>) {
+        Bbow::insert(self, key);
+    }
+}
+
+/// Count `words` into `bag`, as [Bbow::count_words_with]
+/// does.
+pub(crate) fn count_words_with<'a, 't, B, I, K>(
+    bag: &mut B,
+    words: I,
+    window: &mut VecDeque<Cow<'a, str>>,
+    keep: K,
+) where
+    B: Bag<'a>,
+    I: IntoIterator<Item = Cow<'t, str>>,
+    K: Fn(Cow<'t, str>) -> Cow<'a, str>,
+{
+    let ngrams = bag.settings().ngrams.clone();
+    for word in words {
+        if let Some(term) = bag.settings_mut().term_with(word, &keep) {
+            for_each_ngram(&ngrams, term, window, |key| bag.insert(key));
+        }
+    }
+}
+
+/// Add `(word, count)` pairs to `bag` as they are, leaving
+/// out counts of 0.
+pub(crate) fn extend<'a, B, I>(bag: &mut B, pairs: I)
+where
+    B: Bag<'a>,
+    I: IntoIterator<Item = (Cow<'a, str>, usize)>,
+{
+    for (word, count) in pairs {
+        if count > 0 {
+            *bag.counts_mut().count_mut(word) += count;
+        }
+    }
+}
+
+/// Add the counts and surface forms of `other` to `bag`.
+pub(crate) fn add<'a, B: Bag<'a>, O: Bag<'a>>(bag: &mut B, other: O) {
+    let (words, surface_forms) = other.into_parts();
+    extend(bag, words);
+    bag.settings_mut().merge_surface_forms(surface_forms);
+}
+
+/// Like [add], copying the keys of `other`.
+pub(crate) fn add_ref<'a, B: Bag<'a>, O: Bag<'a>>(bag: &mut B, other: &O) {
+    extend(
+        bag,
+        other
+            .counts()
+            .pairs()
+            .map(|(word, count)| (word.clone(), count)),
+    );
+    let surface_forms = other.settings().surface_forms.clone();
+    bag.settings_mut().merge_surface_forms(surface_forms);
+}
+
+/// Subtract the counts of `other` from `bag`, removing the
+/// words whose count drops to zero.
+pub(crate) fn subtract<'a, 'b, B: Bag<'a>, O: Bag<'b>>(bag: &mut B, other: &O) {
+    let other = other.counts();
+    bag.counts_mut().retain(|word, count| {
+        *count = count.saturating_sub(other.get(word).unwrap_or(0));
+        *count > 0
+    });
+    prune_surface_forms(bag);
+}
+
+/// Combine `other` into `bag`, keeping the larger count of
+/// each word.
+pub(crate) fn union<'a, B: Bag<'a>, O: Bag<'a>>(bag: &mut B, other: O) {
+    let (words, surface_forms) = other.into_parts();
+    for (word, count) in words {
+        let current = bag.counts_mut().count_mut(word);
+        *current = cmp::max(*current, count);
+    }
+    bag.settings_mut().merge_surface_forms(surface_forms);
+}
+
+/// Keep only the words of `bag` also in `other`, with the
+/// smaller of the two counts.
+pub(crate) fn intersection<'a, 'b, B: Bag<'a>, O: Bag<'b>>(bag: &mut B, other: &O) {
+    let other = other.counts();
+    bag.counts_mut()
+        .retain(|word, count| match other.get(word) {
+            Some(other_count) => {
+                *count = cmp::min(*count, other_count);
+                true
+            }
+            None => false,
+        });
+    prune_surface_forms(bag);
+}
+
+/// Remove the given `stop_words` from `bag`, as
+/// [Bbow::remove_stop_words] does.
+pub(crate) fn remove_stop_words<'a, B: Bag<'a>>(bag: &mut B, stop_words: &StopWords) {
+    let stop_terms = bag.settings().stop_terms(stop_words);
+    bag.counts_mut()
+        .retain(|key, _| !contains_any_term(key, &stop_terms));
+    prune_surface_forms(bag);
+}
+
+/// Drop the surface forms of stems no longer in `bag`.
+pub(crate) fn prune_surface_forms<'a, B: Bag<'a>>(bag: &mut B) {
+    let Some(mut surface_forms) = bag.settings_mut().surface_forms.take() else {
+        return;
+    };
+    surface_forms.retain(|stem, _| bag.counts().get(stem).is_some());
+    bag.settings_mut().surface_forms = Some(surface_forms);
+}
+
+/// The (at most) `k` words of `bag` with the largest counts,
+/// as [Bbow::most_common] lists them.
+pub(crate) fn most_common<'s, 'a: 's, B: Bag<'a>>(bag: &'s B, k: usize) -> Vec<(&'s str, usize)> {
+    let ranked = bag
+        .counts()
+        .pairs()
+        .map(|(word, count)| (Reverse(count), word.as_ref()));
+    smallest(ranked, k)
+        .into_iter()
+        .map(|(Reverse(count), word)| (word, count))
+        .collect()
+}
+
+/// The (at most) `k` words of `bag` with the smallest
+/// counts, as [Bbow::least_common] lists them.
+pub(crate) fn least_common<'s, 'a: 's, B: Bag<'a>>(bag: &'s B, k: usize) -> Vec<(&'s str, usize)> {
+    let ranked = bag
+        .counts()
+        .pairs()
+        .map(|(word, count)| (count, word.as_ref()));
+    smallest(ranked, k)
+        .into_iter()
+        .map(|(count, word)| (word, count))
+        .collect()
+}
+
+/// Is any of the terms of `key`, a word or n-gram, one of
+/// `terms`?
+fn contains_any_term(key: &str, terms: &BTreeSet<Cow<'_, str>>) -> bool {
+    key.split(' ').any(|term| terms.contains(term))
+}
+
+/// The `k` smallest of `items`, in increasing order, keeping
+/// only `k` of them in memory at a time.
+fn smallest<T: Ord>(items: impl Iterator<Item = T>, k: usize) -> Vec<T> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for item in items {
+        heap.push(item);
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+}
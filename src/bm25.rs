@@ -0,0 +1,214 @@
+//! Ranked keyword search over a [Corpus] with
+//! [Okapi BM25](https://en.wikipedia.org/wiki/Okapi_BM25).
+//!
+//! BM25 scores a document by how often it contains each
+//! query word, weighted by how rare the word is in the
+//! corpus. Repeated occurrences count for less and less, and
+//! long documents (as measured by [Bbow::count]) are
+//! penalized for having more chances to contain a word.
+//! BM25+ adds a floor to the contribution of each matched
+//! word, so that very long documents are not penalized too
+//! harshly.
+
+use std::cmp::Ordering;
+
+use crate::{Bbow, Corpus};
+
+/// A BM25 scorer over a [Corpus].
+#[derive(Debug, Clone)]
+pub struct Bm25<'c, 'a> {
+    corpus: &'c Corpus<'a>,
+    k1: f64,
+    b: f64,
+    delta: f64,
+    average_length: f64,
+}
+
+impl<'c, 'a> Bm25<'c, 'a> {
+    /// Make a BM25 scorer for `corpus` with the usual
+    /// parameters `k1 = 1.2` and `b = 0.75`.
+    pub fn new(corpus: &'c Corpus<'a>) -> Self {
+        let total: usize = corpus.documents().map(|(_, bag)| bag.count()).sum();
+        let average_length = if corpus.is_empty() {
+            0.0
+        } else {
+            total as f64 / corpus.len() as f64
+        };
+        Bm25 {
+            corpus,
+            k1: 1.2,
+            b: 0.75,
+            delta: 0.0,
+            average_length,
+        }
+    }
+
+    /// Set the term frequency saturation `k1`: the larger it
+    /// is, the more repeated occurrences of a word count.
+    pub fn k1(mut self, k1: f64) -> Self {
+        self.k1 = k1;
+        self
+    }
+
+    /// Set the length normalization `b`, from 0 (document
+    /// length is ignored) to 1 (scores are fully scaled by
+    /// relative document length).
+    pub fn b(mut self, b: f64) -> Self {
+        self.b = b;
+        self
+    }
+
+    /// Score with BM25+, adding `delta` (typically 1) to the
+    /// contribution of each matched word. A `delta` of 0 is
+    /// plain BM25.
+    pub fn plus(mut self, delta: f64) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// The BM25 inverse document frequency of a word found in
+    /// `df` documents. This form is never negative.
+    fn idf(&self, df: usize) -> f64 {
+        let (n, df) = (self.corpus.len() as f64, df as f64);
+        ((n - df + 0.5) / (df + 0.5) + 1.0).ln()
+    }
+
+    /// Score `bag` against the keys of `query`.
+    fn score(&self, bag: &Bbow<'_>, query: &Bbow<'_>) -> f64 {
+        let length = bag.count() as f64;
+        let norm = if self.average_length > 0.0 {
+            1.0 - self.b + self.b * length / self.average_length
+        } else {
+            1.0
+        };
+        query
+            .words
+            .keys()
+            .filter_map(|key| {
+                let tf = *bag.words.get(key)? as f64;
+                let idf = self.idf(self.corpus.key_frequency(key));
+                let saturated = tf * (self.k1 + 1.0) / (tf + self.k1 * norm);
+                Some(idf * (saturated + self.delta))
+            })
+            .sum()
+    }
+
+    /// The BM25 score of the document with the given `id`
+    /// for `query`, or `None` if there is no such document.
+    pub fn document_score(&self, id: &str, query: &str) -> Option<f64> {
+        let query = self.query(query);
+        Some(self.score(self.corpus.document(id)?, &query))
+    }
+
+    /// Find the (at most) `top_k` documents best matching
+    /// `query`, best first, with their scores. Ties are
+    /// broken by document id. Documents containing none of
+    /// the query words are not returned.
+    ///
+    /// The query is split into words with the same rules as
+    /// [Bbow::extend_from_text], using the corpus' template
+    /// settings.
+    pub fn search(&self, query: &str, top_k: usize) -> Vec<(&'c str, f64)> {
+        let query = self.query(query);
+        let mut results: Vec<_> = self
+            .corpus
+            .documents()
+            .filter(|(_, bag)| query.words.keys().any(|key| bag.words.contains_key(key)))
+            .map(|(id, bag)| (id, self.score(bag, &query)))
+            .collect();
+        results.sort_by(|(id1, s1), (id2, s2)| {
+            s2.partial_cmp(s1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| id1.cmp(id2))
+        });
+        results.truncate(top_k);
+        results
+    }
+
+    /// Build a bag of the words of `query`.
+    fn query<'q>(&self, query: &'q str) -> Bbow<'q> {
+        self.corpus.template().empty_clone().extend_from_text(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{close, corpus};
+
+    const DOCUMENTS: &[(&str, &str)] = &[
+        ("cats", "The cat sat on the mat. The cat purred."),
+        ("dogs", "The dog sat on the log."),
+        ("both", "A cat and a dog."),
+        ("none", "Nothing to see here."),
+    ];
+
+    #[test]
+    fn test_search() {
+        let corpus = corpus(DOCUMENTS);
+        let bm25 = Bm25::new(&corpus);
+        let ids: Vec<_> = bm25
+            .search("Cat!", 10)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(vec!["cats", "both"], ids);
+        let ids: Vec<_> = bm25
+            .search("cat dog", 10)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!("both", ids[0]);
+        assert_eq!(3, ids.len());
+        assert!(bm25.search("bird", 10).is_empty());
+    }
+
+    #[test]
+    fn test_top_k() {
+        let corpus = corpus(DOCUMENTS);
+        let results = Bm25::new(&corpus).search("the", 1);
+        assert_eq!(1, results.len());
+    }
+
+    #[test]
+    fn test_score() {
+        let corpus = corpus(DOCUMENTS);
+        // Lengths: cats 9, dogs 6, both 5, none 4: average 6.
+        let bm25 = Bm25::new(&corpus);
+        let idf = (1.0f64 + (4.0 - 1.0 + 0.5) / (1.0 + 0.5)).ln();
+        let norm = 1.0 - 0.75 + 0.75 * 6.0 / 6.0;
+        let expected = idf * 1.0 * 2.2 / (1.0 + 1.2 * norm);
+        assert!(close(expected, bm25.document_score("dogs", "log").unwrap()));
+        assert!(close(0.0, bm25.document_score("cats", "log").unwrap()));
+        assert!(bm25.document_score("birds", "log").is_none());
+    }
+
+    #[test]
+    fn test_no_length_normalization() {
+        let corpus = corpus(DOCUMENTS);
+        let bm25 = Bm25::new(&corpus).b(0.0);
+        let cats = bm25.document_score("cats", "sat").unwrap();
+        let dogs = bm25.document_score("dogs", "sat").unwrap();
+        assert!(close(cats, dogs));
+        let bm25 = Bm25::new(&corpus);
+        assert!(bm25.document_score("cats", "sat") < bm25.document_score("dogs", "sat"));
+    }
+
+    #[test]
+    fn test_plus() {
+        let corpus = corpus(DOCUMENTS);
+        let plain = Bm25::new(&corpus).document_score("dogs", "log").unwrap();
+        let plus = Bm25::new(&corpus)
+            .plus(1.0)
+            .document_score("dogs", "log")
+            .unwrap();
+        let idf = (1.0f64 + (4.0 - 1.0 + 0.5) / (1.0 + 0.5)).ln();
+        assert!(close(plain + idf, plus));
+    }
+
+    #[test]
+    fn test_empty_corpus() {
+        let corpus = Corpus::new();
+        assert!(Bm25::new(&corpus).search("cat", 10).is_empty());
+    }
+}
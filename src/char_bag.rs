@@ -0,0 +1,181 @@
+//! A bag of character n-grams.
+//!
+//! Where a [Bbow](crate::Bbow) counts words, a [CharBag]
+//! counts the short runs of characters inside words. Such
+//! counts are useful for identifying the language of a text,
+//! and for matching words despite typos.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::{Normalizer, Tokenizer, WordTokenizer};
+
+/// Each key in this struct's map is a sequence of characters
+/// occurring in the words of some text, and the
+/// corresponding value is the count of occurrences.
+///
+/// Words are found and normalized as for a
+/// [Bbow](crate::Bbow), then padded with a [CharBag::PAD] space on
+/// each side, so that `"it"` yields the trigrams `" it"` and
+/// `"it "`. N-grams never span two words.
+#[derive(Debug, Clone)]
+pub struct CharBag {
+    grams: BTreeMap<String, usize>,
+    normalizer: Normalizer,
+    range: RangeInclusive<usize>,
+}
+
+impl Default for CharBag {
+    fn default() -> Self {
+        CharBag {
+            grams: BTreeMap::new(),
+            normalizer: Normalizer::default(),
+            range: 3..=5,
+        }
+    }
+}
+
+impl CharBag {
+    /// Character marking the start and end of a word in a
+    /// character n-gram.
+    pub const PAD: char = ' ';
+
+    /// Make a new empty bag counting 3- to 5-grams.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count n-grams of `n` characters for each `n` in
+    /// `range`, counting the padding. This should be set
+    /// before any text is added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty or includes 0.
+    pub fn with_range(mut self, range: RangeInclusive<usize>) -> Self {
+        assert!(
+            *range.start() > 0 && range.start() <= range.end(),
+            "invalid n-gram range {:?}",
+            range
+        );
+        self.range = range;
+        self
+    }
+
+    /// Use `normalizer` to normalize words before they are
+    /// split into n-grams. This should be set before any
+    /// text is added.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Parse the `target` text and add the n-grams of its
+    /// words to this bag. This is a builder method, like
+    /// [Bbow::extend_from_text](crate::Bbow::extend_from_text).
+    pub fn extend_from_text(self, target: &str) -> Self {
+        self.extend_with(&WordTokenizer::new(), target)
+    }
+
+    /// Split the `target` text into words using the given
+    /// `tokenizer` and add their n-grams to this bag.
+    pub fn extend_with<T: Tokenizer + ?Sized>(mut self, tokenizer: &T, target: &str) -> Self {
+        for word in tokenizer.tokens(target) {
+            let word = self.normalizer.normalize(word);
+            for gram in char_ngrams(&word, self.range.clone()) {
+                *self.grams.entry(gram).or_insert(0) += 1;
+            }
+        }
+        self
+    }
+
+    /// Report the number of occurrences of the n-gram
+    /// `gram`, which is normalized as words are. Use [CharBag::PAD]
+    /// to mark word boundaries: `match_count(" th")` counts
+    /// words starting with "th".
+    pub fn match_count(&self, gram: &str) -> usize {
+        let gram = self.normalizer.normalize(Cow::Borrowed(gram));
+        self.grams.get(gram.as_ref()).copied().unwrap_or(0)
+    }
+
+    /// The n-grams in this bag, in sorted order.
+    pub fn grams(&self) -> impl Iterator<Item = &str> {
+        self.grams.keys().map(|g| g.as_str())
+    }
+
+    /// The n-grams of exactly `n` characters in this bag, in
+    /// sorted order.
+    pub fn ngrams(&self, n: usize) -> impl Iterator<Item = &str> {
+        self.grams().filter(move |g| g.chars().count() == n)
+    }
+
+    /// Count the overall number of n-grams contained in this
+    /// bag: multiple occurrences are considered separate.
+    pub fn count(&self) -> usize {
+        self.grams.values().sum()
+    }
+
+    /// Count the number of unique n-grams contained in this
+    /// bag, not considering number of occurrences.
+    pub fn len(&self) -> usize {
+        self.grams.len()
+    }
+
+    /// Is this bag empty?
+    pub fn is_empty(&self) -> bool {
+        self.grams.is_empty()
+    }
+}
+
+/// The padded character n-grams of `word` with lengths in
+/// `range`. A padded word shorter than `n` yields no n-grams
+/// of that length.
+pub(crate) fn char_ngrams(word: &str, range: RangeInclusive<usize>) -> Vec<String> {
+    let chars: Vec<char> = std::iter::once(CharBag::PAD)
+        .chain(word.chars())
+        .chain(std::iter::once(CharBag::PAD))
+        .collect();
+    range
+        .flat_map(|n| chars.windows(n).map(|w| w.iter().collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_ngrams() {
+        assert_eq!(vec![" it", "it "], char_ngrams("it", 3..=3));
+        assert_eq!(vec![" it "], char_ngrams("it", 4..=5));
+        assert_eq!(vec![" ï", "ïl", "l "], char_ngrams("ïl", 2..=2));
+    }
+
+    #[test]
+    fn test_extend_from_text() {
+        let bag = CharBag::new()
+            .with_range(3..=3)
+            .extend_from_text("The theme, then!");
+        assert_eq!(3, bag.match_count(" th"));
+        assert_eq!(3, bag.match_count("THE"));
+        assert_eq!(1, bag.match_count("hem"));
+        assert_eq!(0, bag.match_count("xyz"));
+        assert_eq!(12, bag.count());
+        assert_eq!(8, bag.len());
+        assert!(!bag.is_empty());
+    }
+
+    #[test]
+    fn test_ngrams() {
+        let bag = CharBag::new().extend_from_text("word");
+        let fives: Vec<_> = bag.ngrams(5).collect();
+        assert_eq!(vec![" word", "word "], fives);
+        assert_eq!(9, bag.len());
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(CharBag::new().extend_from_text("42 !").is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+//! A collection of documents, each reduced to a [Bbow].
+//!
+//! A single bag can say how often a word occurs in one
+//! document, but not how rare the word is across documents.
+//! A [Corpus] keeps track of document frequencies (the
+//! number of documents containing each word) and uses them
+//! to weight words by TF-IDF.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::Bbow;
+
+/// Each document in a corpus has a bag of words, keyed by a
+/// document id.
+#[derive(Debug, Clone, Default)]
+pub struct Corpus<'a> {
+    documents: BTreeMap<String, Bbow<'a>>,
+    document_frequencies: BTreeMap<Cow<'a, str>, usize>,
+    template: Bbow<'a>,
+}
+
+impl<'a> Corpus<'a> {
+    /// Make a new empty corpus.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the bags of documents added with
+    /// [Corpus::add_text] with the settings (normalizer, stop
+    /// words, etc.) of `template`, and look up words the same
+    /// way. Any words already in `template` are ignored.
+    pub fn with_template(mut self, template: Bbow<'a>) -> Self {
+        self.template = template.empty_clone();
+        self
+    }
+
+    /// Add the document with the given `id` and `bag`. If a
+    /// document with that id was already present, it is
+    /// replaced and its bag is returned.
+    pub fn add_document<S: Into<String>>(&mut self, id: S, bag: Bbow<'a>) -> Option<Bbow<'a>> {
+        for word in bag.words.keys() {
+            *self.document_frequencies.entry(word.clone()).or_insert(0) += 1;
+        }
+        let old = self.documents.insert(id.into(), bag);
+        if let Some(old) = &old {
+            self.forget(old);
+        }
+        old
+    }
+
+    /// Add the document with the given `id`, building its
+    /// bag from `text`. See [Corpus::add_document].
+    pub fn add_text<S: Into<String>>(&mut self, id: S, text: &'a str) -> Option<Bbow<'a>> {
+        let bag = self.template.empty_clone().extend_from_text(text);
+        self.add_document(id, bag)
+    }
+
+    /// Remove the document with the given `id`, returning its
+    /// bag if it was present.
+    pub fn remove_document(&mut self, id: &str) -> Option<Bbow<'a>> {
+        let old = self.documents.remove(id)?;
+        self.forget(&old);
+        Some(old)
+    }
+
+    /// Remove the words of a departing document from the
+    /// document frequencies.
+    fn forget(&mut self, bag: &Bbow<'a>) {
+        for word in bag.words.keys() {
+            if let Some(df) = self.document_frequencies.get_mut(word) {
+                *df -= 1;
+                if *df == 0 {
+                    self.document_frequencies.remove(word);
+                }
+            }
+        }
+    }
+
+    /// The bag of the document with the given `id`.
+    pub fn document(&self, id: &str) -> Option<&Bbow<'a>> {
+        self.documents.get(id)
+    }
+
+    /// The ids and bags of the documents in this corpus, in
+    /// id order.
+    pub fn documents(&self) -> impl Iterator<Item = (&str, &Bbow<'a>)> {
+        self.documents.iter().map(|(id, bag)| (id.as_str(), bag))
+    }
+
+    /// Number of documents in this corpus.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Is this corpus empty?
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// The settings used for this corpus' bags.
+    pub(crate) fn template(&self) -> &Bbow<'a> {
+        &self.template
+    }
+
+    /// Number of documents containing `keyword`, which is
+    /// normalized as by [Bbow::match_count].
+    pub fn document_frequency(&self, keyword: &str) -> usize {
+        match self.template.key(keyword) {
+            Ok(Some(key)) => self.key_frequency(&key),
+            _ => 0,
+        }
+    }
+
+    /// Number of documents containing the bag key `key`.
+    pub(crate) fn key_frequency(&self, key: &str) -> usize {
+        self.document_frequencies.get(key).copied().unwrap_or(0)
+    }
+
+    /// The inverse document frequency of `keyword`, as
+    /// computed by `weighting`.
+    pub fn idf(&self, keyword: &str, weighting: &TfIdf) -> f64 {
+        weighting.idf_of(self.document_frequency(keyword), self.len())
+    }
+
+    /// The TF-IDF weights of the words of the document with
+    /// the given `id`, in word order.
+    pub fn tf_idf(&self, id: &str, weighting: &TfIdf) -> Option<Vec<(&str, f64)>> {
+        let bag = self.documents.get(id)?;
+        let total = bag.count();
+        let max = bag.words.values().copied().max().unwrap_or(0);
+        let weights = bag
+            .words
+            .iter()
+            .map(|(word, &count)| {
+                let tf = weighting.tf_of(count, total, max);
+                let idf = weighting.idf_of(self.key_frequency(word), self.len());
+                (word.as_ref(), tf * idf)
+            })
+            .collect();
+        Some(weights)
+    }
+
+    /// The TF-IDF weights of every document, in id order.
+    pub fn tf_idf_all<'s>(
+        &'s self,
+        weighting: &'s TfIdf,
+    ) -> impl Iterator<Item = (&'s str, Vec<(&'s str, f64)>)> + 's {
+        self.documents
+            .keys()
+            .filter_map(move |id| Some((id.as_str(), self.tf_idf(id, weighting)?)))
+    }
+}
+
+/// How the count of a word in a document is turned into its
+/// term frequency.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Tf {
+    /// The count itself.
+    #[default]
+    Raw,
+    /// 1 if the word occurs at all.
+    Binary,
+    /// The count divided by the number of words in the
+    /// document.
+    Frequency,
+    /// 0.5 plus half the count divided by the count of the
+    /// document's most common word, which damps the effect
+    /// of long documents.
+    Augmented,
+}
+
+/// How the document frequency of a word is turned into its
+/// inverse document frequency. `N` is the number of
+/// documents in the corpus and `df` the number containing
+/// the word.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Idf {
+    /// Always 1: weights are plain term frequencies.
+    Unary,
+    /// `ln(N / df)`, or `ln((1 + N) / (1 + df)) + 1` with
+    /// smoothing.
+    #[default]
+    Standard,
+    /// `ln((N - df) / df)`, or
+    /// `ln((N - df + 0.5) / (df + 0.5))` with smoothing;
+    /// never negative.
+    Probabilistic,
+}
+
+/// A TF-IDF weighting scheme.
+///
+/// The default scheme uses raw counts, smoothed standard
+/// IDF, and no sublinear scaling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TfIdf {
+    tf: Tf,
+    idf: Idf,
+    smooth: bool,
+    sublinear: bool,
+}
+
+impl Default for TfIdf {
+    fn default() -> Self {
+        TfIdf {
+            tf: Tf::default(),
+            idf: Idf::default(),
+            smooth: true,
+            sublinear: false,
+        }
+    }
+}
+
+impl TfIdf {
+    /// Make the default weighting scheme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the term frequency variant.
+    pub fn tf(mut self, tf: Tf) -> Self {
+        self.tf = tf;
+        self
+    }
+
+    /// Set the inverse document frequency variant.
+    pub fn idf(mut self, idf: Idf) -> Self {
+        self.idf = idf;
+        self
+    }
+
+    /// Smooth the inverse document frequency, as if one
+    /// extra document contained every word. This avoids
+    /// dividing by zero for words in no document.
+    pub fn smooth(mut self, smooth: bool) -> Self {
+        self.smooth = smooth;
+        self
+    }
+
+    /// Replace each count `c` by `1 + ln(c)` before
+    /// computing term frequencies, so that a word occurring
+    /// twenty times does not weigh twenty times as much as
+    /// one occurring once.
+    pub fn sublinear_tf(mut self, sublinear: bool) -> Self {
+        self.sublinear = sublinear;
+        self
+    }
+
+    fn scale(&self, count: usize) -> f64 {
+        match count {
+            0 => 0.0,
+            c if self.sublinear => 1.0 + (c as f64).ln(),
+            c => c as f64,
+        }
+    }
+
+    /// Term frequency of a word occurring `count` times in
+    /// a document of `total` words whose most common word
+    /// occurs `max` times.
+    fn tf_of(&self, count: usize, total: usize, max: usize) -> f64 {
+        let tf = self.scale(count);
+        match self.tf {
+            Tf::Raw => tf,
+            Tf::Binary => (count > 0) as u8 as f64,
+            Tf::Frequency if total == 0 => 0.0,
+            Tf::Frequency => tf / self.scale(total),
+            Tf::Augmented if max == 0 => 0.0,
+            Tf::Augmented => 0.5 + 0.5 * tf / self.scale(max),
+        }
+    }
+
+    /// Inverse document frequency of a word in `df` of `n`
+    /// documents.
+    fn idf_of(&self, df: usize, n: usize) -> f64 {
+        let (df, n) = (df as f64, n as f64);
+        match (self.idf, self.smooth) {
+            (Idf::Unary, _) => 1.0,
+            (Idf::Standard, true) => ((1.0 + n) / (1.0 + df)).ln() + 1.0,
+            (Idf::Standard, false) if df == 0.0 => 0.0,
+            (Idf::Standard, false) => (n / df).ln(),
+            (Idf::Probabilistic, true) => ((n - df + 0.5) / (df + 0.5)).ln().max(0.0),
+            (Idf::Probabilistic, false) if df == 0.0 => 0.0,
+            (Idf::Probabilistic, false) => ((n - df) / df).ln().max(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{close, corpus};
+    use crate::{Language, StopWords};
+
+    const DOCUMENTS: &[(&str, &str)] = &[
+        ("one", "the cat sat on the mat"),
+        ("two", "the dog sat"),
+        ("three", "The cat ran"),
+    ];
+
+    fn weight(weights: &[(&str, f64)], word: &str) -> f64 {
+        weights.iter().find(|(w, _)| *w == word).unwrap().1
+    }
+
+    #[test]
+    fn test_document_frequency() {
+        let corpus = corpus(DOCUMENTS);
+        assert_eq!(3, corpus.len());
+        assert_eq!(3, corpus.document_frequency("The"));
+        assert_eq!(2, corpus.document_frequency("cat"));
+        assert_eq!(1, corpus.document_frequency("mat"));
+        assert_eq!(0, corpus.document_frequency("bird"));
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut corpus = corpus(DOCUMENTS);
+        let old = corpus.add_text("two", "a bird sat");
+        assert_eq!(1, old.unwrap().match_count("dog"));
+        assert_eq!(0, corpus.document_frequency("dog"));
+        assert_eq!(1, corpus.document_frequency("bird"));
+        assert_eq!(2, corpus.document_frequency("the"));
+        corpus.remove_document("one");
+        assert_eq!(1, corpus.document_frequency("cat"));
+        assert_eq!(2, corpus.len());
+        assert!(corpus.remove_document("one").is_none());
+    }
+
+    #[test]
+    fn test_template() {
+        let template = Bbow::new()
+            .with_stop_words(StopWords::language(Language::English))
+            .extend_from_text("ignored words");
+        let mut corpus = Corpus::new().with_template(template);
+        corpus.add_text("one", "the cat sat on the mat");
+        assert_eq!(3, corpus.document("one").unwrap().len());
+        assert_eq!(0, corpus.document_frequency("ignored"));
+    }
+
+    #[test]
+    fn test_tf_idf_default() {
+        let corpus = corpus(DOCUMENTS);
+        let weights = corpus.tf_idf("one", &TfIdf::new()).unwrap();
+        let words: Vec<_> = weights.iter().map(|(w, _)| *w).collect();
+        assert_eq!(vec!["cat", "mat", "on", "sat", "the"], words);
+        assert!(close(2.0, weight(&weights, "the")));
+        assert!(close((4.0f64 / 3.0).ln() + 1.0, weight(&weights, "cat")));
+        assert!(close(2.0f64.ln() + 1.0, weight(&weights, "mat")));
+    }
+
+    #[test]
+    fn test_tf_idf_unsmoothed() {
+        let corpus = corpus(DOCUMENTS);
+        let weighting = TfIdf::new().smooth(false);
+        let weights = corpus.tf_idf("one", &weighting).unwrap();
+        assert!(close(0.0, weight(&weights, "the")));
+        assert!(close(3.0f64.ln(), weight(&weights, "mat")));
+        assert!(close(0.0, corpus.idf("bird", &weighting)));
+    }
+
+    #[test]
+    fn test_tf_variants() {
+        let corpus = corpus(DOCUMENTS);
+        let tf = |weighting: TfIdf| {
+            let weights = corpus.tf_idf("one", &weighting.idf(Idf::Unary)).unwrap();
+            weight(&weights, "the")
+        };
+        assert!(close(2.0, tf(TfIdf::new())));
+        assert!(close(1.0, tf(TfIdf::new().tf(Tf::Binary))));
+        assert!(close(2.0 / 6.0, tf(TfIdf::new().tf(Tf::Frequency))));
+        assert!(close(1.0, tf(TfIdf::new().tf(Tf::Augmented))));
+        assert!(close(
+            1.0 + 2.0f64.ln(),
+            tf(TfIdf::new().sublinear_tf(true))
+        ));
+    }
+
+    #[test]
+    fn test_probabilistic_idf() {
+        let corpus = corpus(DOCUMENTS);
+        let weighting = TfIdf::new().idf(Idf::Probabilistic).smooth(false);
+        assert!(close(2.0f64.ln(), corpus.idf("mat", &weighting)));
+        assert!(close(0.0, corpus.idf("the", &weighting)));
+    }
+
+    #[test]
+    fn test_tf_idf_all() {
+        let corpus = corpus(DOCUMENTS);
+        let weighting = TfIdf::new();
+        let ids: Vec<_> = corpus.tf_idf_all(&weighting).map(|(id, _)| id).collect();
+        assert_eq!(vec!["one", "three", "two"], ids);
+        assert!(corpus.tf_idf("four", &weighting).is_none());
+    }
+}
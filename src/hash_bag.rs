@@ -0,0 +1,578 @@
+//! A bag of words backed by a hash map.
+//!
+//! A [Bbow] keeps its words in a `BTreeMap`, so they are
+//! always in sorted order, but each word counted costs a
+//! tree search with string comparisons. A [HashBbow] counts
+//! words into a `HashMap` instead, which is faster when
+//! counting large texts, and can be turned into a [Bbow]
+//! once counting is done: `cargo bench` compares them.
+//! Any `BuildHasher` can be used in place of the standard
+//! library's, through [HashBbow::with_hasher]: a fast
+//! non-cryptographic hasher such as the `rustc-hash` crate's
+//! `FxBuildHasher` speeds counting up further, at the cost
+//! of no protection against inputs crafted to collide. The
+//! benchmark measures it too.
+//!
+//! A [HashBbow] has the same methods as a [Bbow] for
+//! building, combining and iterating over it and for looking
+//! up words, but its words are visited in no particular
+//! order. Comparing bags, weighting and vectorizing their
+//! words and serializing them are left to [Bbow]: convert a
+//! [HashBbow] with `Bbow::from` first.
+
+use std::borrow::Cow;
+use std::collections::hash_map::{self, RandomState};
+use std::collections::{HashMap, VecDeque};
+use std::hash::BuildHasher;
+use std::iter::FusedIterator;
+use std::ops::{Add, AddAssign, RangeInclusive, Sub, SubAssign};
+
+use crate::bag::{self, Bag};
+use crate::parallel::{par_extend, ShardBag};
+use crate::{
+    ngram_order, Bbow, KeywordError, Normalizer, Stemmer, StopWords, SurfaceForms, Tokenizer,
+    WordTokenizer,
+};
+
+/// A bag of words counted into a `HashMap` with hasher
+/// builder `S`.
+#[derive(Debug, Clone, Default)]
+pub struct HashBbow<'a, S = RandomState> {
+    words: HashMap<Cow<'a, str>, usize, S>,
+    /// The settings used to analyze words, and the surface
+    /// forms recorded. Its own map is always empty.
+    template: Bbow<'a>,
+}
+
+impl HashBbow<'_> {
+    /// Make a new empty hash-backed BBOW.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a, S: BuildHasher> HashBbow<'a, S> {
+    /// Make a new empty hash-backed BBOW using `hasher` to
+    /// hash its words.
+    pub fn with_hasher(hasher: S) -> Self {
+        HashBbow {
+            words: HashMap::with_hasher(hasher),
+            template: Bbow::new(),
+        }
+    }
+
+    /// Use `normalizer` to map words to their keys, as with
+    /// [Bbow::with_normalizer].
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.template = self.template.with_normalizer(normalizer);
+        self
+    }
+
+    /// Drop the given `stop_words`, as with
+    /// [Bbow::with_stop_words].
+    pub fn with_stop_words(mut self, stop_words: StopWords) -> Self {
+        self.template = self.template.with_stop_words(stop_words);
+        self
+    }
+
+    /// Reduce each word to its stem, as with
+    /// [Bbow::with_stemmer].
+    pub fn with_stemmer<T: Stemmer + 'static>(mut self, stemmer: T) -> Self {
+        self.template = self.template.with_stemmer(stemmer);
+        self
+    }
+
+    /// Record, for each stem, the words that were reduced to
+    /// it, as with [Bbow::keep_surface_forms].
+    pub fn keep_surface_forms(mut self, keep: bool) -> Self {
+        self.template = self.template.keep_surface_forms(keep);
+        self
+    }
+
+    /// Count n-grams, as with [Bbow::with_ngrams].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty or includes 0.
+    pub fn with_ngrams(mut self, range: RangeInclusive<usize>) -> Self {
+        self.template = self.template.with_ngrams(range);
+        self
+    }
+
+    /// Remove any of the given `stop_words` that are already
+    /// in this BBOW, as with [Bbow::remove_stop_words].
+    pub fn remove_stop_words(mut self, stop_words: &StopWords) -> Self {
+        bag::remove_stop_words(&mut self, stop_words);
+        self
+    }
+
+    /// Parse the `target` text and add its words to this
+    /// BBOW, as with [Bbow::extend_from_text].
+    pub fn extend_from_text(self, target: &'a str) -> Self {
+        self.extend_with(&WordTokenizer::new(), target)
+    }
+
+    /// Split the `target` text into words using the given
+    /// `tokenizer` and add them to this BBOW, as with
+    /// [Bbow::extend_with].
+    pub fn extend_with<T: Tokenizer + ?Sized>(mut self, tokenizer: &T, target: &'a str) -> Self {
+        let mut window = VecDeque::with_capacity(*self.template.ngrams.end());
+        bag::count_words_with(&mut self, tokenizer.tokens(target), &mut window, |w| w);
+        self
+    }
+
+    /// Report the number of occurrences of `keyword`, as
+    /// with [Bbow::match_count].
+    pub fn match_count(&self, keyword: &str) -> usize {
+        self.try_match_count(keyword).unwrap_or(0)
+    }
+
+    /// Like [HashBbow::match_count], but report an error if
+    /// the `keyword` is not a valid word, as with
+    /// [Bbow::try_match_count].
+    pub fn try_match_count(&self, keyword: &str) -> Result<usize, KeywordError> {
+        let count = self
+            .template
+            .lookup(keyword, |key| self.words.get(key).copied())?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// The words that were stemmed to the stem of `keyword`,
+    /// in sorted order, as with [Bbow::surface_forms].
+    pub fn surface_forms(&self, keyword: &str) -> impl Iterator<Item = &str> {
+        self.template.surface_forms(keyword)
+    }
+
+    /// The n-grams of exactly `n` words in this BBOW, in no
+    /// particular order, as with [Bbow::ngrams].
+    pub fn ngrams(&self, n: usize) -> impl Iterator<Item = &str> {
+        self.words().filter(move |w: &&str| ngram_order(w) == n)
+    }
+
+    /// The words in this BBOW, in no particular order.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.keys().map(|w| w.as_ref())
+    }
+
+    /// Iterate over the words of this BBOW with their
+    /// counts, in no particular order.
+    pub fn iter(&self) -> HashIter<'_, 'a> {
+        HashIter {
+            inner: self.words.iter(),
+        }
+    }
+
+    /// The (at most) `k` words with the largest counts, as
+    /// with [Bbow::most_common]. Words with equal counts are
+    /// listed in sorted order.
+    pub fn most_common(&self, k: usize) -> Vec<(&str, usize)> {
+        bag::most_common(self, k)
+    }
+
+    /// The (at most) `k` words with the smallest counts, as
+    /// with [Bbow::least_common].
+    pub fn least_common(&self, k: usize) -> Vec<(&str, usize)> {
+        bag::least_common(self, k)
+    }
+
+    /// Count the overall number of words contained in this
+    /// BBOW: multiple occurrences are considered separate.
+    pub fn count(&self) -> usize {
+        self.words.values().sum()
+    }
+
+    /// Count the number of unique words contained in this
+    /// BBOW.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Is this BBOW empty?
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Combine with `other`, keeping the larger count of each
+    /// word, as with [Bbow::union].
+    pub fn union<T: BuildHasher>(mut self, other: HashBbow<'a, T>) -> Self {
+        bag::union(&mut self, other);
+        self
+    }
+
+    /// Keep only words also in `other`, with the smaller of
+    /// the two counts, as with [Bbow::intersection].
+    pub fn intersection<T: BuildHasher>(mut self, other: &HashBbow<'_, T>) -> Self {
+        bag::intersection(&mut self, other);
+        self
+    }
+}
+
+impl<'a, S: BuildHasher> Bag<'a> for HashBbow<'a, S> {
+    type Map = HashMap<Cow<'a, str>, usize, S>;
+
+    fn counts(&self) -> &Self::Map {
+        &self.words
+    }
+
+    fn counts_mut(&mut self) -> &mut Self::Map {
+        &mut self.words
+    }
+
+    fn settings(&self) -> &Bbow<'a> {
+        &self.template
+    }
+
+    fn settings_mut(&mut self) -> &mut Bbow<'a> {
+        &mut self.template
+    }
+
+    fn into_parts(self) -> (Self::Map, Option<SurfaceForms<'a>>) {
+        (self.words, self.template.surface_forms)
+    }
+}
+
+impl<'a, S: BuildHasher + Clone + Send> HashBbow<'a, S> {
+    /// Parse the `target` text and add its words to this
+    /// BBOW on up to `threads` threads, as with
+    /// [Bbow::par_extend_from_text].
+    pub fn par_extend_from_text(self, target: &'a str, threads: usize) -> Self {
+        self.par_extend_with(&WordTokenizer::new(), target, threads)
+    }
+
+    /// Like [HashBbow::par_extend_from_text], but split the
+    /// text into words with `tokenizer`, as with
+    /// [Bbow::par_extend_with].
+    pub fn par_extend_with<T>(mut self, tokenizer: &T, target: &'a str, threads: usize) -> Self
+    where
+        T: Tokenizer + Sync + ?Sized,
+    {
+        if threads <= 1 {
+            return self.extend_with(tokenizer, target);
+        }
+        par_extend(&mut self, tokenizer, target, threads);
+        self
+    }
+}
+
+impl<'a, S: BuildHasher + Clone + Send> ShardBag<'a> for HashBbow<'a, S> {
+    fn empty(&self) -> Self {
+        HashBbow {
+            words: HashMap::with_hasher(self.words.hasher().clone()),
+            template: self.template.empty_clone(),
+        }
+    }
+}
+
+/// An iterator over the `(word, count)` pairs of a
+/// [HashBbow], in no particular order. See [HashBbow::iter].
+#[derive(Debug, Clone)]
+pub struct HashIter<'s, 'a> {
+    inner: hash_map::Iter<'s, Cow<'a, str>, usize>,
+}
+
+impl<'s> Iterator for HashIter<'s, '_> {
+    type Item = (&'s str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(word, &count)| (word.as_ref(), count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for HashIter<'_, '_> {}
+
+impl FusedIterator for HashIter<'_, '_> {}
+
+/// An owning iterator over the `(word, count)` pairs of a
+/// [HashBbow], in no particular order.
+#[derive(Debug)]
+pub struct HashIntoIter<'a> {
+    inner: hash_map::IntoIter<Cow<'a, str>, usize>,
+}
+
+impl<'a> Iterator for HashIntoIter<'a> {
+    type Item = (Cow<'a, str>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for HashIntoIter<'_> {}
+
+impl FusedIterator for HashIntoIter<'_> {}
+
+impl<'s, 'a, S: BuildHasher> IntoIterator for &'s HashBbow<'a, S> {
+    type Item = (&'s str, usize);
+    type IntoIter = HashIter<'s, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, S> IntoIterator for HashBbow<'a, S> {
+    type Item = (Cow<'a, str>, usize);
+    type IntoIter = HashIntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        HashIntoIter {
+            inner: self.words.into_iter(),
+        }
+    }
+}
+
+/// Add `(word, count)` pairs as they are, as with the
+/// `Extend` impls of [Bbow].
+impl<'a, S: BuildHasher> Extend<(Cow<'a, str>, usize)> for HashBbow<'a, S> {
+    fn extend<I: IntoIterator<Item = (Cow<'a, str>, usize)>>(&mut self, pairs: I) {
+        bag::extend(self, pairs);
+    }
+}
+
+impl<'a, S: BuildHasher> Extend<(&'a str, usize)> for HashBbow<'a, S> {
+    fn extend<I: IntoIterator<Item = (&'a str, usize)>>(&mut self, pairs: I) {
+        self.extend(
+            pairs
+                .into_iter()
+                .map(|(word, count)| (Cow::Borrowed(word), count)),
+        );
+    }
+}
+
+impl<'a, S: BuildHasher + Default> FromIterator<(Cow<'a, str>, usize)> for HashBbow<'a, S> {
+    fn from_iter<I: IntoIterator<Item = (Cow<'a, str>, usize)>>(pairs: I) -> Self {
+        let mut bag = HashBbow::with_hasher(S::default());
+        bag.extend(pairs);
+        bag
+    }
+}
+
+impl<'a, S: BuildHasher + Default> FromIterator<(&'a str, usize)> for HashBbow<'a, S> {
+    fn from_iter<I: IntoIterator<Item = (&'a str, usize)>>(pairs: I) -> Self {
+        let mut bag = HashBbow::with_hasher(S::default());
+        bag.extend(pairs);
+        bag
+    }
+}
+
+/// The sum of two bags: counts are added.
+impl<'a, S: BuildHasher> Add for HashBbow<'a, S> {
+    type Output = HashBbow<'a, S>;
+
+    fn add(mut self, other: HashBbow<'a, S>) -> Self::Output {
+        self += other;
+        self
+    }
+}
+
+impl<'a, S: BuildHasher> AddAssign for HashBbow<'a, S> {
+    fn add_assign(&mut self, other: HashBbow<'a, S>) {
+        bag::add(self, other);
+    }
+}
+
+impl<'a, S: BuildHasher> AddAssign<&HashBbow<'a, S>> for HashBbow<'a, S> {
+    fn add_assign(&mut self, other: &HashBbow<'a, S>) {
+        bag::add_ref(self, other);
+    }
+}
+
+/// The difference of two bags, as for [Bbow]: words whose
+/// count would drop to zero or below are removed.
+impl<'a, S: BuildHasher, T: BuildHasher> Sub<&HashBbow<'_, T>> for HashBbow<'a, S> {
+    type Output = HashBbow<'a, S>;
+
+    fn sub(mut self, other: &HashBbow<'_, T>) -> Self::Output {
+        self -= other;
+        self
+    }
+}
+
+impl<'a, S: BuildHasher> Sub for HashBbow<'a, S> {
+    type Output = HashBbow<'a, S>;
+
+    fn sub(self, other: HashBbow<'a, S>) -> Self::Output {
+        self - &other
+    }
+}
+
+impl<S: BuildHasher, T: BuildHasher> SubAssign<&HashBbow<'_, T>> for HashBbow<'_, S> {
+    fn sub_assign(&mut self, other: &HashBbow<'_, T>) {
+        bag::subtract(self, other);
+    }
+}
+
+/// Sort the words of a [HashBbow] into a [Bbow] with the
+/// same settings and surface forms.
+impl<'a, S> From<HashBbow<'a, S>> for Bbow<'a> {
+    fn from(bag: HashBbow<'a, S>) -> Self {
+        let mut sorted = bag.template;
+        sorted.words = bag.words.into_iter().collect();
+        sorted
+    }
+}
+
+/// Move the words of a [Bbow] into a [HashBbow] with the
+/// same settings and surface forms.
+impl<'a, S: BuildHasher + Default> From<Bbow<'a>> for HashBbow<'a, S> {
+    fn from(mut bag: Bbow<'a>) -> Self {
+        let words = std::mem::take(&mut bag.words);
+        HashBbow {
+            words: words.into_iter().collect(),
+            template: bag,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::{extend_shards, shards};
+    use crate::test_util::pairs;
+    use crate::Language;
+    use rustc_hash::FxBuildHasher;
+
+    const TEXT: &str = "It ain't over untïl it ain't, over. Can't stop this! Stop!";
+
+    /// The pairs of `bag`, in sorted order.
+    fn sorted<S>(bag: HashBbow<'_, S>) -> Vec<(String, usize)> {
+        pairs(&Bbow::from(bag))
+    }
+
+    #[test]
+    fn test_same_as_bbow() {
+        let expected = Bbow::new()
+            .with_ngrams(1..=2)
+            .with_stop_words(StopWords::language(Language::English))
+            .extend_from_text(TEXT);
+        let hashed = HashBbow::new()
+            .with_ngrams(1..=2)
+            .with_stop_words(StopWords::language(Language::English))
+            .extend_from_text(TEXT);
+        assert_eq!(expected.len(), hashed.len());
+        assert_eq!(expected.count(), hashed.count());
+        assert_eq!(2, hashed.match_count("Stop!"));
+        assert_eq!(1, hashed.match_count("untïl stop"));
+        assert_eq!(0, hashed.match_count("this"));
+        assert_eq!(Ok(0), hashed.try_match_count("banana"));
+        assert_eq!(Err(KeywordError::NotAWord), hashed.try_match_count("..."));
+        assert_eq!(expected.ngrams(2).count(), hashed.ngrams(2).count());
+        let sorted = Bbow::from(hashed);
+        assert!(sorted.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let hashed = HashBbow::with_hasher(FxBuildHasher)
+            .with_ngrams(1..=2)
+            .extend_from_text(TEXT);
+        assert_eq!(2, hashed.match_count("stop"));
+        assert_eq!(1, hashed.match_count("stop this"));
+        let expected = HashBbow::new().with_ngrams(1..=2).extend_from_text(TEXT);
+        assert_eq!(sorted(expected), sorted(hashed));
+    }
+
+    #[test]
+    fn test_most_common() {
+        let bag = HashBbow::new().extend_from_text("c b a b c c d d e");
+        assert_eq!(vec![("c", 3), ("b", 2), ("d", 2)], bag.most_common(3));
+        assert_eq!(vec![("a", 1), ("e", 1)], bag.least_common(2));
+    }
+
+    #[test]
+    fn test_from_bbow() {
+        let bag = Bbow::new().with_ngrams(1..=2).extend_from_text("stop this");
+        let hashed: HashBbow = bag.into();
+        let hashed = hashed.extend_from_text("stop this");
+        assert_eq!(2, hashed.match_count("stop this"));
+        assert_eq!(6, hashed.count());
+    }
+
+    #[test]
+    fn test_surface_forms() {
+        #[derive(Debug)]
+        struct TrimS;
+        impl Stemmer for TrimS {
+            fn stem<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+                match word.strip_suffix('s') {
+                    Some(stem) => Cow::Owned(stem.to_string()),
+                    None => word,
+                }
+            }
+        }
+        let bag = HashBbow::new()
+            .with_stemmer(TrimS)
+            .keep_surface_forms(true)
+            .extend_from_text("tests test stops");
+        assert_eq!(
+            vec!["test", "tests"],
+            bag.surface_forms("tests").collect::<Vec<_>>()
+        );
+        let bag = bag.remove_stop_words(&["stop"].into_iter().collect());
+        assert_eq!(0, bag.surface_forms("stop").count());
+        let sorted = Bbow::from(bag);
+        assert_eq!(2, sorted.surface_forms("test").count());
+    }
+
+    #[test]
+    fn test_multiset_operations() {
+        let a = || HashBbow::new().extend_from_text("a a b");
+        let b = || HashBbow::new().extend_from_text("b c");
+        let expected = |counts: &[(&str, usize)]| -> Vec<(String, usize)> {
+            counts.iter().map(|&(w, c)| (w.to_string(), c)).collect()
+        };
+        assert_eq!(expected(&[("a", 2), ("b", 2), ("c", 1)]), sorted(a() + b()));
+        assert_eq!(
+            expected(&[("a", 2), ("b", 1), ("c", 1)]),
+            sorted(a().union(b()))
+        );
+        assert_eq!(expected(&[("b", 1)]), sorted(a().intersection(&b())));
+        assert_eq!(expected(&[("a", 2)]), sorted(a() - b()));
+        let mut sum = a();
+        sum += &b();
+        assert_eq!(expected(&[("a", 2), ("b", 2), ("c", 1)]), sorted(sum));
+    }
+
+    #[test]
+    fn test_iteration() {
+        let bag = HashBbow::new().extend_from_text("stop this stop");
+        let mut counts: Vec<_> = (&bag).into_iter().collect();
+        counts.sort_unstable();
+        assert_eq!(vec![("stop", 2), ("this", 1)], counts);
+        assert_eq!(2, bag.iter().len());
+
+        let collected: HashBbow = bag.clone().into_iter().collect();
+        assert_eq!(2, collected.match_count("stop"));
+        let mut extended: HashBbow = [("stop", 1), ("test", 0)].into_iter().collect();
+        extended.extend(bag);
+        assert_eq!(3, extended.match_count("stop"));
+        assert_eq!(2, extended.len());
+    }
+
+    #[test]
+    fn test_par_extend() {
+        let template = || {
+            HashBbow::new()
+                .with_ngrams(1..=3)
+                .with_stop_words(StopWords::language(Language::English))
+        };
+        let expected = sorted(template().extend_from_text(TEXT));
+        for count in 1..=12 {
+            let mut bag = template();
+            extend_shards(&mut bag, &WordTokenizer::new(), shards(TEXT, count));
+            assert_eq!(expected, sorted(bag), "{} shards", count);
+        }
+        let bag = template().par_extend_from_text(TEXT, usize::MAX);
+        assert_eq!(expected, sorted(bag));
+    }
+}
@@ -0,0 +1,243 @@
+//! Feature hashing: vectors of fixed size without a
+//! vocabulary.
+//!
+//! A [HashingVectorizer] maps each word of a bag to one of a
+//! fixed number of columns by hashing it, so no vocabulary
+//! has to be built or stored, and words never seen before
+//! still get a column. Different words may share a column;
+//! to keep such collisions from adding up, each word also
+//! gets a sign from its hash, so colliding counts tend to
+//! cancel out rather than pile up.
+//!
+//! Words are hashed with 32-bit MurmurHash3 (seed 0) of
+//! their UTF-8 bytes, which does not depend on the platform
+//! or the run. The column and sign are chosen from the hash
+//! as scikit-learn's `HashingVectorizer` chooses them.
+
+use std::collections::BTreeMap;
+
+use crate::{Bbow, Corpus, CsrMatrix, Norm, SparseVector, Tokenizer, WordTokenizer};
+
+/// 32-bit MurmurHash3 (x86 variant) of `bytes`.
+fn murmur3_32(bytes: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    let mut hash = seed;
+    let mut blocks = bytes.chunks_exact(4);
+    for block in &mut blocks {
+        let k = u32::from_le_bytes(block.try_into().expect("block of 4 bytes"));
+        hash ^= mix(k);
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0;
+        for (i, &byte) in tail.iter().enumerate() {
+            k |= u32::from(byte) << (8 * i);
+        }
+        hash ^= mix(k);
+    }
+
+    // The length is mixed in modulo 2^32, as in the reference
+    // implementation.
+    hash ^= bytes.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash
+}
+
+/// Turns bags into sparse vectors of a fixed dimension by
+/// hashing their words.
+#[derive(Debug, Clone)]
+pub struct HashingVectorizer {
+    dimension: usize,
+    alternate_sign: bool,
+    norm: Option<Norm>,
+    template: Bbow<'static>,
+}
+
+impl Default for HashingVectorizer {
+    /// A vectorizer with 2^20 columns.
+    fn default() -> Self {
+        HashingVectorizer::new(1 << 20)
+    }
+}
+
+impl HashingVectorizer {
+    /// Make a vectorizer producing vectors of `dimension`
+    /// entries, with signed buckets and no normalization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dimension` is 0 or greater than 2^32.
+    pub fn new(dimension: usize) -> Self {
+        assert!(
+            dimension > 0 && dimension as u64 <= 1 << 32,
+            "invalid dimension {}",
+            dimension
+        );
+        HashingVectorizer {
+            dimension,
+            alternate_sign: true,
+            norm: None,
+            template: Bbow::new(),
+        }
+    }
+
+    /// Give each word a sign from its hash (the default), or
+    /// add all counts with a positive sign.
+    pub fn alternate_sign(mut self, alternate: bool) -> Self {
+        self.alternate_sign = alternate;
+        self
+    }
+
+    /// Scale each vector to length 1 under `norm`, or leave
+    /// it as it is for `None` (the default).
+    pub fn norm(mut self, norm: Option<Norm>) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    /// Normalize, filter and stem the words of raw text with
+    /// the settings of `template`, as
+    /// [Corpus::with_template] does.
+    pub fn with_template(mut self, template: Bbow<'_>) -> Self {
+        self.template = template.empty_clone();
+        self
+    }
+
+    /// Number of entries in the vectors produced.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// The column of `word`, and the sign of its counts.
+    fn bucket(&self, word: &str) -> (u32, f64) {
+        // As a signed 32-bit value, the hash picks the column
+        // with its magnitude and the sign with its sign.
+        let hash = murmur3_32(word.as_bytes(), 0) as i32;
+        let column = (u64::from(hash.unsigned_abs()) % self.dimension as u64) as u32;
+        let sign = if self.alternate_sign && hash < 0 {
+            -1.0
+        } else {
+            1.0
+        };
+        (column, sign)
+    }
+
+    /// The hashed vector of the word counts of `bag`.
+    pub fn transform(&self, bag: &Bbow<'_>) -> SparseVector {
+        let mut columns = BTreeMap::new();
+        for (word, count) in bag.iter() {
+            let (column, sign) = self.bucket(word);
+            *columns.entry(column).or_insert(0.0) += sign * count as f64;
+        }
+        let vector = SparseVector::from_pairs(self.dimension, columns);
+        match self.norm {
+            Some(norm) => vector.normalize(norm),
+            None => vector,
+        }
+    }
+
+    /// The hashed vector of the words of `text`, split and
+    /// normalized as by [Bbow::extend_from_text] with the
+    /// template settings.
+    pub fn transform_text(&self, text: &str) -> SparseVector {
+        self.transform_text_with(&WordTokenizer::new(), text)
+    }
+
+    /// Like [HashingVectorizer::transform_text], but split
+    /// `text` into words with `tokenizer`.
+    pub fn transform_text_with<T: Tokenizer + ?Sized>(
+        &self,
+        tokenizer: &T,
+        text: &str,
+    ) -> SparseVector {
+        self.transform(&self.template.empty_clone().extend_with(tokenizer, text))
+    }
+
+    /// The hashed vectors of the documents of `corpus` as a
+    /// matrix, with one row per document in id order.
+    pub fn transform_corpus(&self, corpus: &Corpus<'_>) -> CsrMatrix {
+        CsrMatrix::from_rows(
+            self.dimension,
+            corpus.documents().map(|(_, bag)| self.transform(bag)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_murmur3() {
+        assert_eq!(0, murmur3_32(b"", 0));
+        assert_eq!(0x514e_28b7, murmur3_32(b"", 1));
+        assert_eq!(0x248b_fa47, murmur3_32(b"hello", 0));
+        assert_eq!(
+            0x2e4f_f723,
+            murmur3_32(b"The quick brown fox jumps over the lazy dog", 0)
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let vectorizer = HashingVectorizer::new(16);
+        let bag = Bbow::new().extend_from_text("Can't stop this! Stop!");
+        let vector = vectorizer.transform(&bag);
+        assert_eq!(16, vector.dimension());
+        let (stop, sign) = vectorizer.bucket("stop");
+        assert_eq!(2.0 * sign, vector.get(stop));
+        assert_eq!(vector, vectorizer.transform_text("Can't stop this! Stop!"));
+        let total: f64 = vector.values().iter().map(|v| v.abs()).sum();
+        assert!(total <= 3.0);
+    }
+
+    #[test]
+    fn test_unsigned_and_normalized() {
+        let vectorizer = HashingVectorizer::new(1 << 10)
+            .alternate_sign(false)
+            .norm(Some(Norm::L1));
+        let vector = vectorizer.transform_text("a b b c c c");
+        assert!(vector.values().iter().all(|&v| v > 0.0));
+        let total: f64 = vector.values().iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_collisions_cancel() {
+        // With one column, every word lands in the same place.
+        let vectorizer = HashingVectorizer::new(1);
+        let words = ["stop", "this", "test", "string", "purposes"];
+        let expected: f64 = words.iter().map(|w| vectorizer.bucket(w).1).sum();
+        let vector = vectorizer.transform_text(&words.join(" "));
+        assert_eq!(expected, vector.get(0));
+    }
+
+    #[test]
+    fn test_corpus() {
+        let mut corpus = Corpus::new();
+        corpus.add_text("a", "stop this");
+        corpus.add_text("b", "test");
+        let vectorizer = HashingVectorizer::new(64);
+        let matrix = vectorizer.transform_corpus(&corpus);
+        assert_eq!(2, matrix.rows());
+        assert_eq!(64, matrix.columns());
+        assert_eq!(vectorizer.transform_text("test"), matrix.row(1).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_dimension() {
+        let _ = HashingVectorizer::new(0);
+    }
+}
@@ -0,0 +1,432 @@
+//! An inverted index over a collection of documents.
+//!
+//! Where a [Bbow] records how often each word occurs in one
+//! text, an [InvertedIndex] records, for each word, which
+//! documents contain it and at which positions. This
+//! answers boolean queries ("documents containing both
+//! `test` and `string`") and phrase queries ("documents
+//! containing `test` immediately followed by `string`").
+//!
+//! Words are found and normalized with the same rules as
+//! [Bbow::extend_from_text]. Positions count only the words
+//! that are kept, so with stop words removed, "stop this
+//! test" has `stop` at position 0 and `test` at position 1.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::{Bbow, Tokenizer, WordTokenizer};
+
+/// The occurrences of a word in one document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Posting {
+    document: usize,
+    positions: Vec<usize>,
+}
+
+impl Posting {
+    /// Number of occurrences of the word in the document.
+    pub fn term_frequency(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Positions of the word in the document, in increasing
+    /// order. The first word of a document is at position 0.
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+}
+
+/// Postings lists for each word of a set of documents.
+///
+/// Documents are identified by string ids, and are numbered
+/// internally in the order they are added. Query results
+/// are returned in that order. The numbers of removed or
+/// replaced documents are reclaimed once they make up half
+/// of all numbers, so the index does not grow as documents
+/// are re-indexed.
+#[derive(Debug, Clone, Default)]
+pub struct InvertedIndex<'a> {
+    /// Document ids by number: `None` for removed documents.
+    ids: Vec<Option<String>>,
+    numbers: BTreeMap<String, usize>,
+    postings: BTreeMap<Cow<'a, str>, Vec<Posting>>,
+    template: Bbow<'a>,
+}
+
+impl<'a> InvertedIndex<'a> {
+    /// Make a new empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalize, filter and stem words with the settings of
+    /// `template`, as [Corpus::with_template](crate::Corpus::with_template)
+    /// does. N-gram settings are ignored.
+    pub fn with_template(mut self, template: Bbow<'a>) -> Self {
+        self.template = template.empty_clone();
+        self
+    }
+
+    /// Index `text` as the document with the given `id`,
+    /// replacing any document already indexed with that id.
+    /// Returns `true` if a document was replaced.
+    pub fn add_text<S: Into<String>>(&mut self, id: S, text: &'a str) -> bool {
+        self.add_text_with(&WordTokenizer::new(), id, text)
+    }
+
+    /// Like [InvertedIndex::add_text], but split `text` into
+    /// words with `tokenizer`.
+    pub fn add_text_with<T, S>(&mut self, tokenizer: &T, id: S, text: &'a str) -> bool
+    where
+        T: Tokenizer + ?Sized,
+        S: Into<String>,
+    {
+        let id = id.into();
+        let replaced = self.remove_document(&id);
+        let document = self.ids.len();
+        let mut positions: BTreeMap<Cow<'a, str>, Vec<usize>> = BTreeMap::new();
+        let terms = tokenizer
+            .tokens(text)
+            .filter_map(|word| self.template.analyze(word));
+        for (position, term) in terms.enumerate() {
+            positions.entry(term).or_default().push(position);
+        }
+        for (term, positions) in positions {
+            self.postings.entry(term).or_default().push(Posting {
+                document,
+                positions,
+            });
+        }
+        self.numbers.insert(id.clone(), document);
+        self.ids.push(Some(id));
+        replaced
+    }
+
+    /// Remove the document with the given `id` from the
+    /// index. Returns `true` if it was present.
+    ///
+    /// This takes time proportional to the size of the
+    /// vocabulary, and from time to time to the size of the
+    /// whole index, to reclaim document numbers.
+    pub fn remove_document(&mut self, id: &str) -> bool {
+        let Some(document) = self.numbers.remove(id) else {
+            return false;
+        };
+        self.ids[document] = None;
+        self.postings.retain(|_, postings| {
+            postings.retain(|posting| posting.document != document);
+            !postings.is_empty()
+        });
+        if self.ids.len() >= 2 * self.numbers.len() {
+            self.compact();
+        }
+        true
+    }
+
+    /// Renumber the documents from 0, in order, dropping the
+    /// numbers of removed documents.
+    fn compact(&mut self) {
+        let mut renumbered = Vec::with_capacity(self.ids.len());
+        let mut ids = Vec::with_capacity(self.numbers.len());
+        for id in self.ids.drain(..) {
+            renumbered.push(ids.len());
+            if id.is_some() {
+                ids.push(id);
+            }
+        }
+        self.ids = ids;
+        for document in self.numbers.values_mut() {
+            *document = renumbered[*document];
+        }
+        for posting in self.postings.values_mut().flatten() {
+            posting.document = renumbered[posting.document];
+        }
+    }
+
+    /// Number of documents in the index.
+    pub fn len(&self) -> usize {
+        self.numbers.len()
+    }
+
+    /// Is the index empty?
+    pub fn is_empty(&self) -> bool {
+        self.numbers.is_empty()
+    }
+
+    /// The postings of `keyword`, which is normalized as by
+    /// [Bbow::match_count], with the ids of their documents.
+    /// A keyword that is not a single word has no postings.
+    pub fn postings(&self, keyword: &str) -> impl Iterator<Item = (&str, &Posting)> {
+        let mut terms = self.terms(keyword).unwrap_or_default();
+        let postings = match terms.len() {
+            1 => self.term_postings(&terms.remove(0)),
+            _ => &[],
+        };
+        postings
+            .iter()
+            .map(|posting| (self.id(posting.document), posting))
+    }
+
+    /// The ids of the documents containing `keyword`.
+    pub fn documents_with(&self, keyword: &str) -> Vec<&str> {
+        self.postings(keyword).map(|(id, _)| id).collect()
+    }
+
+    /// The ids of the documents containing every one of the
+    /// `keywords`.
+    pub fn all_of(&self, keywords: &[&str]) -> Vec<&str> {
+        let mut sets = keywords
+            .iter()
+            .map(|keyword| self.keyword_documents(keyword));
+        let first = sets.next().unwrap_or_default();
+        self.ids_of(&sets.fold(first, |acc, set| intersect(&acc, &set)))
+    }
+
+    /// The ids of the documents containing any of the
+    /// `keywords`.
+    pub fn any_of(&self, keywords: &[&str]) -> Vec<&str> {
+        let documents = keywords
+            .iter()
+            .map(|keyword| self.keyword_documents(keyword))
+            .fold(Vec::new(), |acc, set| union(&acc, &set));
+        self.ids_of(&documents)
+    }
+
+    /// The ids of the documents containing the words of
+    /// `phrase` consecutively, such as `"test string"`. A
+    /// phrase with a part that is not a word, such as
+    /// `"b-banana split"`, matches nothing.
+    pub fn phrase(&self, phrase: &str) -> Vec<&str> {
+        let terms = self.terms(phrase).unwrap_or_default();
+        self.ids_of(&self.phrase_documents(&terms))
+    }
+
+    /// Split `text` into terms as documents are, or `None`
+    /// if some part of it is not a word and so can never be
+    /// indexed.
+    pub(crate) fn terms<'t>(&self, text: &'t str) -> Option<Vec<Cow<'t, str>>> {
+        let words: Vec<_> = WordTokenizer::new().tokens(text).collect();
+        if words.len() != text.split_whitespace().count() {
+            return None;
+        }
+        let terms = words
+            .into_iter()
+            .filter_map(|word| self.template.analyze(word));
+        Some(terms.collect())
+    }
+
+    fn term_postings(&self, term: &str) -> &[Posting] {
+        self.postings
+            .get(term)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Numbers of the documents containing `term`, in order.
+    pub(crate) fn term_documents(&self, term: &str) -> Vec<usize> {
+        self.term_postings(term)
+            .iter()
+            .map(|p| p.document)
+            .collect()
+    }
+
+    /// Numbers of the documents containing the single term
+    /// of `keyword`, if it has one.
+    fn keyword_documents(&self, keyword: &str) -> Vec<usize> {
+        match self.terms(keyword).as_deref() {
+            Some([term]) => self.term_documents(term),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Numbers of the documents containing `terms`
+    /// consecutively. An empty phrase matches nothing.
+    pub(crate) fn phrase_documents(&self, terms: &[Cow<'_, str>]) -> Vec<usize> {
+        let Some((first, rest)) = terms.split_first() else {
+            return Vec::new();
+        };
+        let lists: Vec<_> = rest.iter().map(|t| self.term_postings(t)).collect();
+        self.term_postings(first)
+            .iter()
+            .filter(|start| {
+                start.positions.iter().any(|&p| {
+                    lists.iter().enumerate().all(|(i, list)| {
+                        find_posting(list, start.document).is_some_and(|posting| {
+                            posting.positions.binary_search(&(p + i + 1)).is_ok()
+                        })
+                    })
+                })
+            })
+            .map(|posting| posting.document)
+            .collect()
+    }
+
+    /// Numbers of all documents in the index, in order.
+    pub(crate) fn all_documents(&self) -> Vec<usize> {
+        let mut documents: Vec<_> = self.numbers.values().copied().collect();
+        documents.sort_unstable();
+        documents
+    }
+
+    /// The terms of the index, in sorted order.
+    pub(crate) fn vocabulary(&self) -> impl Iterator<Item = &str> {
+        self.postings.keys().map(|t| t.as_ref())
+    }
+
+    /// The terms of the index starting with `prefix`, in
+    /// sorted order.
+    pub(crate) fn terms_with_prefix<'s>(
+        &'s self,
+        prefix: &'s str,
+    ) -> impl Iterator<Item = &'s str> {
+        self.postings
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(term, _)| term.as_ref())
+            .take_while(move |term: &&str| term.starts_with(prefix))
+    }
+
+    /// The settings used to analyze documents.
+    pub(crate) fn template(&self) -> &Bbow<'a> {
+        &self.template
+    }
+
+    fn id(&self, document: usize) -> &str {
+        self.ids[document]
+            .as_deref()
+            .expect("posting for removed document")
+    }
+
+    /// The ids of the given document numbers.
+    pub(crate) fn ids_of(&self, documents: &[usize]) -> Vec<&str> {
+        documents.iter().map(|&d| self.id(d)).collect()
+    }
+}
+
+/// The posting for `document` in a postings list, if any.
+fn find_posting(postings: &[Posting], document: usize) -> Option<&Posting> {
+    let i = postings
+        .binary_search_by_key(&document, |p| p.document)
+        .ok()?;
+    Some(&postings[i])
+}
+
+/// Intersection of two sorted lists of document numbers.
+pub(crate) fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    a.iter()
+        .copied()
+        .filter(|d| b.binary_search(d).is_ok())
+        .collect()
+}
+
+/// Documents in sorted list `a` but not in sorted list `b`.
+pub(crate) fn difference(a: &[usize], b: &[usize]) -> Vec<usize> {
+    a.iter()
+        .copied()
+        .filter(|d| b.binary_search(d).is_err())
+        .collect()
+}
+
+/// Union of two sorted lists of document numbers.
+pub(crate) fn union(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut result: Vec<_> = a.iter().chain(b).copied().collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::index;
+    use crate::{Language, StopWords};
+
+    const DOCUMENTS: &[(&str, &str)] = &[
+        ("a", "This is a test string for test purposes"),
+        ("b", "String test: a test, stringently."),
+        ("c", "Can't stop this! Stop!"),
+    ];
+
+    #[test]
+    fn test_postings() {
+        let index = index(DOCUMENTS);
+        let postings: Vec<_> = index
+            .postings("Test")
+            .map(|(id, p)| (id, p.term_frequency(), p.positions().to_vec()))
+            .collect();
+        assert_eq!(vec![("a", 2, vec![3, 6]), ("b", 2, vec![1, 3])], postings);
+        assert_eq!(0, index.postings("test string").count());
+        assert_eq!(0, index.postings("missing").count());
+    }
+
+    #[test]
+    fn test_boolean() {
+        let index = index(DOCUMENTS);
+        assert_eq!(vec!["a", "b"], index.all_of(&["test", "string"]));
+        assert_eq!(vec!["a", "c"], index.all_of(&["this"]));
+        assert!(index.all_of(&["test", "stop"]).is_empty());
+        assert_eq!(
+            vec!["a", "b", "c"],
+            index.any_of(&["purposes", "stop", "string"])
+        );
+        assert!(index.any_of(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_phrase() {
+        let index = index(DOCUMENTS);
+        assert_eq!(vec!["a"], index.phrase("test string"));
+        assert_eq!(vec!["b"], index.phrase("string test"));
+        assert_eq!(vec!["a", "b"], index.phrase("Test"));
+        assert_eq!(vec!["a"], index.phrase("test purposes"));
+        assert!(index.phrase("purposes test").is_empty());
+        assert!(index.phrase("").is_empty());
+        assert!(index.phrase("b-banana string").is_empty());
+        assert_eq!(vec!["c"], index.phrase("stop this"));
+        assert!(index.phrase("can't stop this").is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut index = index(DOCUMENTS);
+        assert!(index.add_text("c", "a new test"));
+        assert_eq!(vec!["a", "b", "c"], index.documents_with("test"));
+        assert!(index.documents_with("stop").is_empty());
+        assert!(index.remove_document("a"));
+        assert!(!index.remove_document("a"));
+        assert_eq!(vec!["b", "c"], index.documents_with("test"));
+        assert_eq!(2, index.len());
+    }
+
+    #[test]
+    fn test_reindex_reclaims_numbers() {
+        let mut index = index(DOCUMENTS);
+        for _ in 0..100 {
+            index.add_text("a", "This is a test string for test purposes");
+        }
+        assert!(index.ids.len() <= 2 * index.len());
+        assert_eq!(vec!["b", "a"], index.documents_with("test"));
+        assert_eq!(vec!["a"], index.phrase("test string"));
+        assert!(index.remove_document("b"));
+        assert!(index.remove_document("c"));
+        assert_eq!(vec![Some("a".to_string())], index.ids);
+        assert_eq!(vec!["a"], index.documents_with("test"));
+    }
+
+    #[test]
+    fn test_template() {
+        let template = Bbow::new().with_stop_words(StopWords::language(Language::English));
+        let mut index = InvertedIndex::new().with_template(template);
+        index.add_text("c", "Can't stop this test!");
+        assert_eq!(vec!["c"], index.phrase("stop this test"));
+        assert!(index.documents_with("this").is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        assert_eq!(vec![2, 5], intersect(&[1, 2, 5], &[2, 3, 5]));
+        assert_eq!(vec![1, 2, 3, 5], union(&[1, 2, 5], &[2, 3, 5]));
+        assert_eq!(vec![1], difference(&[1, 2, 5], &[2, 3, 5]));
+    }
+}
@@ -0,0 +1,191 @@
+//! Iteration over the `(word, count)` pairs of a bag.
+//!
+//! A [Bbow] can be iterated by reference or by value, and
+//! built from or extended with `(word, count)` pairs. Pairs
+//! are taken to be words as stored in a bag: they are added
+//! as they are, without being trimmed, normalized, filtered
+//! or stemmed. Counts for the same word are added together,
+//! and words with a count of 0 are left out.
+
+use std::borrow::Cow;
+use std::collections::btree_map;
+use std::iter::FusedIterator;
+
+use crate::{bag, Bbow};
+
+/// An iterator over the `(word, count)` pairs of a [Bbow],
+/// in sorted word order. See [Bbow::iter].
+#[derive(Debug, Clone)]
+pub struct Iter<'s, 'a> {
+    inner: btree_map::Iter<'s, Cow<'a, str>, usize>,
+}
+
+impl<'s> Iterator for Iter<'s, '_> {
+    type Item = (&'s str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(word, &count)| (word.as_ref(), count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_, '_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|(word, &count)| (word.as_ref(), count))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_, '_> {}
+
+impl FusedIterator for Iter<'_, '_> {}
+
+/// An owning iterator over the `(word, count)` pairs of a
+/// [Bbow], in sorted word order.
+#[derive(Debug)]
+pub struct IntoIter<'a> {
+    inner: btree_map::IntoIter<Cow<'a, str>, usize>,
+}
+
+impl<'a> Iterator for IntoIter<'a> {
+    type Item = (Cow<'a, str>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter<'_> {}
+
+impl FusedIterator for IntoIter<'_> {}
+
+impl<'a> Bbow<'a> {
+    /// Iterate over the words of this BBOW with their counts,
+    /// in sorted word order.
+    pub fn iter(&self) -> Iter<'_, 'a> {
+        Iter {
+            inner: self.words.iter(),
+        }
+    }
+}
+
+impl<'s, 'a> IntoIterator for &'s Bbow<'a> {
+    type Item = (&'s str, usize);
+    type IntoIter = Iter<'s, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for Bbow<'a> {
+    type Item = (Cow<'a, str>, usize);
+    type IntoIter = IntoIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.words.into_iter(),
+        }
+    }
+}
+
+impl<'a> Extend<(Cow<'a, str>, usize)> for Bbow<'a> {
+    fn extend<I: IntoIterator<Item = (Cow<'a, str>, usize)>>(&mut self, pairs: I) {
+        bag::extend(self, pairs);
+    }
+}
+
+impl<'a> Extend<(&'a str, usize)> for Bbow<'a> {
+    fn extend<I: IntoIterator<Item = (&'a str, usize)>>(&mut self, pairs: I) {
+        self.extend(
+            pairs
+                .into_iter()
+                .map(|(word, count)| (Cow::Borrowed(word), count)),
+        );
+    }
+}
+
+impl<'a> FromIterator<(Cow<'a, str>, usize)> for Bbow<'a> {
+    fn from_iter<I: IntoIterator<Item = (Cow<'a, str>, usize)>>(pairs: I) -> Self {
+        let mut bag = Bbow::new();
+        bag.extend(pairs);
+        bag
+    }
+}
+
+impl<'a> FromIterator<(&'a str, usize)> for Bbow<'a> {
+    fn from_iter<I: IntoIterator<Item = (&'a str, usize)>>(pairs: I) -> Self {
+        let mut bag = Bbow::new();
+        bag.extend(pairs);
+        bag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iter() {
+        let bag = Bbow::new().extend_from_text("Can't stop this! Stop!");
+        let pairs: Vec<_> = bag.iter().collect();
+        assert_eq!(vec![("stop", 2), ("this", 1)], pairs);
+        assert_eq!(2, bag.iter().len());
+        assert_eq!(Some(("this", 1)), bag.iter().next_back());
+        let mut total = 0;
+        for (_, count) in &bag {
+            total += count;
+        }
+        assert_eq!(bag.count(), total);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let text = "Can't stop this! Stop!";
+        let bag = Bbow::new().extend_from_text(text);
+        let pairs: Vec<_> = bag.into_iter().collect();
+        assert_eq!(Cow::Borrowed("this"), pairs[1].0);
+        assert!(matches!(pairs[1].0, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let bag: Bbow = vec![("b", 1), ("a", 2), ("b", 3), ("c", 0)]
+            .into_iter()
+            .collect();
+        assert_eq!(vec![("a", 2), ("b", 4)], bag.iter().collect::<Vec<_>>());
+
+        let copy: Bbow = bag.clone().into_iter().collect();
+        assert_eq!(
+            bag.iter().collect::<Vec<_>>(),
+            copy.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut bag = Bbow::new().extend_from_text("stop this");
+        bag.extend([("stop", 2), ("test", 1)]);
+        bag.extend([(Cow::Owned("test".to_string()), 1)]);
+        assert_eq!(
+            vec![("stop", 3), ("test", 2), ("this", 1)],
+            bag.iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -7,12 +7,21 @@
 //! This implementation uses zero-copy strings when
 //! reasonably possible to improve performance and reduce
 //! memory usage.
+//! [Bbow::into_owned] and [OwnedBbow] copy the words of a
+//! bag when it must outlive its text. An [OwnedBbow] can
+//! also count the words of a stream too large to hold in
+//! memory: see [OwnedBbow::extend_from_reader]. A long text
+//! can be counted on several threads with
+//! [Bbow::par_extend_from_text], and a [HashBbow] counts
+//! faster than a [Bbow] by giving up sorted order until it
+//! is converted.
 //!
 //! Words are separated by whitespace, and consist of a
 //! span of one or more consecutive letters (any Unicode
 //! code point in the "letter" class) with no internal
-//! punctuation: leading and trailing punctuation are
-//! removed.
+//! punctuation: leading and trailing punctuation (any code
+//! point in a Unicode punctuation class, including quotes,
+//! dashes and brackets) are removed.
 //!
 //! For example, the text
 //!
@@ -24,23 +33,224 @@
 //! `"untïl"`, `"it"`, `"over"`.
 //!
 //! Words in the bag containing uppercase letters will be
-//! represented by their lowercase equivalent.
+//! represented by their lowercase equivalent. A [Normalizer]
+//! can be attached to a bag to change this: it can also
+//! fold case fully, bring words into Unicode normal form
+//! and strip diacritics. Common function words can be
+//! left out of a bag by attaching a [StopWords] set, and
+//! inflected forms can be counted together by attaching a
+//! [Stemmer] (the `stemming` feature provides Snowball
+//! stemmers).
+//!
+//! The words of a bag can be visited with their counts
+//! through [Bbow::iter], and bags can be collected from
+//! `(word, count)` pairs.
+//!
+//! Bags can be combined with the usual multiset
+//! operations: `+` adds counts, `-` subtracts them, and
+//! [Bbow::union] and [Bbow::intersection] take the larger
+//! and smaller count of each word.
+//! Bags can also be compared: see
+//! [Bbow::cosine_similarity] and its neighbors.
+//!
+//! A [Corpus] collects the bags of many documents, and
+//! weights their words by TF-IDF. A [Bm25] scorer ranks
+//! the documents of a corpus against a keyword query, and an
+//! [InvertedIndex] records word positions for boolean and
+//! phrase queries. A [Query] combines words, prefixes,
+//! fuzzy words and phrases with `AND`, `OR` and `NOT`, and
+//! can be run against an index or a single bag.
+//!
+//! A [CharBag] counts character n-grams of words rather
+//! than the words themselves.
+//!
+//! Many bags can share a [Vocabulary] that numbers their
+//! words, and store only word numbers as an [IdBbow]. A
+//! vocabulary also fixes the columns of feature vectors: see
+//! [Bbow::to_sparse] and [Corpus::to_csr]. A
+//! [HashingVectorizer] makes feature vectors of a fixed size
+//! without a vocabulary.
+//!
+//! Other splitting rules can be supplied through the
+//! [Tokenizer] trait and [Bbow::extend_with]. With the
+//! `segmentation` feature enabled, `SegmentTokenizer`
+//! splits text at Unicode (UAX #29) word boundaries instead
+//! of at whitespace.
+//!
+//! Neither tokenizer knows the words of languages written
+//! without spaces, such as Thai, Chinese or Japanese. By
+//! default a whole run of such text between spaces is one
+//! word; `SegmentTokenizer` splits it into single
+//! characters instead. Counting the real words of these
+//! languages needs a dictionary-based [Tokenizer].
+//!
+//! With the `serde` feature enabled, bags can be serialized
+//! as maps from words to counts, and deserialized as owned
+//! bags.
+
+mod bag;
+mod bm25;
+mod char_bag;
+mod corpus;
+mod hash_bag;
+mod hashing;
+mod index;
+mod iter;
+mod normalize;
+mod ops;
+mod owned;
+mod parallel;
+mod query;
+mod reader;
+#[cfg(feature = "serde")]
+mod serialize;
+mod similarity;
+mod sparse;
+mod stem;
+mod stop_words;
+#[cfg(test)]
+mod test_util;
+mod tokenizer;
+mod vocabulary;
+
+pub use bm25::Bm25;
+pub use char_bag::CharBag;
+pub use corpus::{Corpus, Idf, Tf, TfIdf};
+pub use hash_bag::{HashBbow, HashIntoIter, HashIter};
+pub use hashing::HashingVectorizer;
+pub use index::{InvertedIndex, Posting};
+pub use iter::{IntoIter, Iter};
+pub use normalize::{CaseMode, NormalForm, Normalizer};
+pub use owned::OwnedBbow;
+pub use query::{Query, QueryError};
+pub use reader::Utf8Policy;
+pub use sparse::{CsrMatrix, Norm, SparseVector};
+pub use stem::Stemmer;
+pub use stop_words::{Language, StopWords};
+pub use tokenizer::{Tokenizer, WordTokenizer};
+pub use vocabulary::{IdBbow, Vocabulary};
+
+#[cfg(feature = "segmentation")]
+pub use tokenizer::SegmentTokenizer;
+
+#[cfg(feature = "stemming")]
+pub use stem::SnowballStemmer;
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Why a keyword could not be looked up in a [Bbow].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordError {
+    /// The keyword contains no valid word.
+    NotAWord,
+    /// The keyword contains this many words: more than the
+    /// longest n-gram counted by the bag.
+    MultipleWords(usize),
+}
+
+impl fmt::Display for KeywordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeywordError::NotAWord => write!(f, "keyword is not a word"),
+            KeywordError::MultipleWords(n) => write!(f, "keyword contains {} words", n),
+        }
+    }
+}
+
+impl std::error::Error for KeywordError {}
+
+/// The surface forms of each stem in a [Bbow].
+type SurfaceForms<'a> = BTreeMap<Cow<'a, str>, BTreeSet<Cow<'a, str>>>;
 
 /// Each key in this struct's map is a word in some
 /// in-memory text document. The corresponding value is the
 /// count of occurrences.
-#[derive(Debug, Default, Clone)]
-pub struct Bbow<'a>(BTreeMap<Cow<'a, str>, usize>);
+///
+/// When n-grams are enabled with [Bbow::with_ngrams], keys
+/// may also be sequences of words separated by a single
+/// space, such as `"test string"`.
+#[derive(Debug, Clone)]
+pub struct Bbow<'a> {
+    words: BTreeMap<Cow<'a, str>, usize>,
+    normalizer: Normalizer,
+    /// The stop words as given, kept so that they can be
+    /// normalized again if the normalizer changes.
+    stop_words: StopWords,
+    /// The stop words as normalized by `normalizer`.
+    stop_keys: StopWords,
+    stemmer: Option<Arc<dyn Stemmer>>,
+    surface_forms: Option<SurfaceForms<'a>>,
+    ngrams: RangeInclusive<usize>,
+}
 
-fn is_word(word: &str) -> bool {
-    !word.is_empty() && word.chars().all(|c| c.is_alphabetic())
+impl Default for Bbow<'_> {
+    fn default() -> Self {
+        Bbow {
+            words: BTreeMap::new(),
+            normalizer: Normalizer::default(),
+            stop_words: StopWords::default(),
+            stop_keys: StopWords::default(),
+            stemmer: None,
+            surface_forms: None,
+            ngrams: 1..=1,
+        }
+    }
 }
 
-fn has_uppercase(word: &str) -> bool {
-    word.chars().any(char::is_uppercase)
+/// Join the terms of an n-gram into a key.
+fn join_terms<'t, I>(terms: I) -> String
+where
+    I: IntoIterator<Item = &'t Cow<'t, str>>,
+{
+    let mut key = String::new();
+    for term in terms {
+        if !key.is_empty() {
+            key.push(' ');
+        }
+        key.push_str(term);
+    }
+    key
+}
+
+/// Push `term` onto `window`, which holds the most recent
+/// terms, and pass each n-gram it ends to `f`, for each
+/// length in `ngrams`.
+fn for_each_ngram<'a, F>(
+    ngrams: &RangeInclusive<usize>,
+    term: Cow<'a, str>,
+    window: &mut VecDeque<Cow<'a, str>>,
+    mut f: F,
+) where
+    F: FnMut(Cow<'a, str>),
+{
+    let (min_n, max_n) = (*ngrams.start(), *ngrams.end());
+    if max_n == 1 {
+        f(term);
+        return;
+    }
+    // The window keeps the most recent `max_n` terms: each
+    // new term ends one n-gram of each length.
+    if window.len() == max_n {
+        window.pop_front();
+    }
+    window.push_back(term);
+    for n in min_n..=window.len() {
+        let key = if n == 1 {
+            window[window.len() - 1].clone()
+        } else {
+            Cow::Owned(join_terms(window.range(window.len() - n..)))
+        };
+        f(key);
+    }
+}
+
+/// Number of words in an n-gram key.
+fn ngram_order(key: &str) -> usize {
+    key.split(' ').count()
 }
 
 impl<'a> Bbow<'a> {
@@ -49,75 +259,325 @@ impl<'a> Bbow<'a> {
         Self::default()
     }
 
+    /// A new empty BBOW with the same settings as this one.
+    /// It need not borrow from the same text.
+    pub(crate) fn empty_clone<'b>(&self) -> Bbow<'b> {
+        Bbow {
+            words: BTreeMap::new(),
+            normalizer: self.normalizer.clone(),
+            stop_words: self.stop_words.clone(),
+            stop_keys: self.stop_keys.clone(),
+            stemmer: self.stemmer.clone(),
+            surface_forms: self.surface_forms.as_ref().map(|_| BTreeMap::new()),
+            ngrams: self.ngrams.clone(),
+        }
+    }
+
+    /// Use `normalizer` to map words to their keys in this
+    /// BBOW. This should be set before any text is added.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self.stop_keys = self.stop_words.normalized(&self.normalizer);
+        self
+    }
+
+    /// Drop the given `stop_words` from text added to this
+    /// BBOW from now on. Stop words are normalized in the
+    /// same way as the words of the text, even if the
+    /// normalizer is set afterwards.
+    pub fn with_stop_words(mut self, stop_words: StopWords) -> Self {
+        self.stop_keys = stop_words.normalized(&self.normalizer);
+        self.stop_words = stop_words;
+        self
+    }
+
+    /// Reduce each word to its stem with `stemmer` before
+    /// counting it. This should be set before any text is
+    /// added.
+    pub fn with_stemmer<S: Stemmer + 'static>(mut self, stemmer: S) -> Self {
+        self.stemmer = Some(Arc::new(stemmer));
+        self
+    }
+
+    /// Record, for each stem, the words that were reduced to
+    /// it. See [Bbow::surface_forms].
+    pub fn keep_surface_forms(mut self, keep: bool) -> Self {
+        self.surface_forms = keep.then(BTreeMap::new);
+        self
+    }
+
+    /// Count the sequences of `n` consecutive words in each
+    /// added text, for each `n` in `range`, rather than just
+    /// single words. `1..=3` counts words, bigrams and
+    /// trigrams; `2..=2` counts bigrams only.
+    ///
+    /// N-grams are formed after stop words are removed, and
+    /// never span two separately added texts.
+    ///
+    /// An n-gram is stored as its words joined by spaces, so
+    /// the words themselves must not contain spaces. The
+    /// built-in tokenizers never yield such words, but a
+    /// custom [Tokenizer] might: its token `"new york"` would
+    /// be taken for a bigram.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty or includes 0.
+    pub fn with_ngrams(mut self, range: RangeInclusive<usize>) -> Self {
+        assert!(
+            *range.start() > 0 && range.start() <= range.end(),
+            "invalid n-gram range {:?}",
+            range
+        );
+        self.ngrams = range;
+        self
+    }
+
+    /// Remove any of the given `stop_words` that are already
+    /// in this BBOW, along with any n-grams containing them.
+    /// The stop words are normalized and stemmed as counted
+    /// words are, so the result is the same as counting with
+    /// [Bbow::with_stop_words], except that n-grams are not
+    /// rejoined across the removed words.
+    ///
+    /// Like [Bbow::extend_from_text], this is a builder method.
+    pub fn remove_stop_words(mut self, stop_words: &StopWords) -> Self {
+        bag::remove_stop_words(&mut self, stop_words);
+        self
+    }
+
+    /// The terms the given `stop_words` are counted as.
+    pub(crate) fn stop_terms<'s>(&self, stop_words: &'s StopWords) -> BTreeSet<Cow<'s, str>> {
+        stop_words
+            .words()
+            .filter_map(|word| self.analyze(Cow::Borrowed(word)))
+            .collect()
+    }
+
     /// Parse the `target` text and add the sequence of
     /// valid words contained in it to this BBOW.
     ///
     /// This is a "builder method": calls can be
     /// conveniently chained to build up a BBOW covering
     /// multiple texts.
-    pub fn extend_from_text(mut self, target: &'a str) -> Self {
-        let string_parts = target.split_whitespace();
-        let punctuation: &[_] = &["!", ".", ",", "?", "/", ";", ":", "'"];
-        for parts in string_parts {
-            let mut part = parts;
-            // I know this is ugly, I tried to get that trim_matches function to work for like half an hour and got annoyed.
-            for p in punctuation {
-                part = part.trim_end_matches(p);
-                part = part.trim_start_matches(p);
-            }
-            if is_word(part) {
-                if has_uppercase(part) {
-                    let lower = part.to_lowercase();
-                    // This line of code was derived from the example at https://doc.rust-lang.org/std/collections/struct.BTreeMap.html#method.entry
-                    self.0
-                        .entry(lower.into())
-                        .and_modify(|curr| *curr += 1)
-                        .or_insert(1);
-                } else {
-                    self.0
-                        .entry(part.into())
-                        .and_modify(|curr| *curr += 1)
-                        .or_insert(1);
-                }
-            }
-        }
+    pub fn extend_from_text(self, target: &'a str) -> Self {
+        self.extend_with(&WordTokenizer::new(), target)
+    }
 
+    /// Split the `target` text into words using the given
+    /// `tokenizer` and add them to this BBOW. Words are
+    /// normalized as usual.
+    ///
+    /// Like [Bbow::extend_from_text], this is a builder method.
+    pub fn extend_with<T: Tokenizer + ?Sized>(mut self, tokenizer: &T, target: &'a str) -> Self {
+        let mut window = VecDeque::with_capacity(*self.ngrams.end());
+        self.count_words(tokenizer.tokens(target), &mut window);
         self
     }
 
+    /// Count `words`, and the n-grams they form. `window`
+    /// holds the most recent terms counted, and is updated
+    /// as new terms are counted: passing the same window to
+    /// successive calls counts n-grams spanning them.
+    pub(crate) fn count_words<I>(&mut self, words: I, window: &mut VecDeque<Cow<'a, str>>)
+    where
+        I: IntoIterator<Item = Cow<'a, str>>,
+    {
+        self.count_words_with(words, window, |word| word);
+    }
+
+    /// Like [Bbow::count_words], but for words that need not
+    /// live as long as this BBOW: `keep` makes them do so,
+    /// and is only applied to the words that are kept, once
+    /// they are normalized.
+    pub(crate) fn count_words_with<'t, I, K>(
+        &mut self,
+        words: I,
+        window: &mut VecDeque<Cow<'a, str>>,
+        keep: K,
+    ) where
+        I: IntoIterator<Item = Cow<'t, str>>,
+        K: Fn(Cow<'t, str>) -> Cow<'a, str>,
+    {
+        bag::count_words_with(self, words, window, keep);
+    }
+
+    /// Normalize, filter and stem `word`, recording its
+    /// surface form if asked to.
+    fn term(&mut self, word: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        self.term_with(word, |word| word)
+    }
+
+    /// Like [Bbow::term], applying `keep` to the terms and
+    /// surface forms that are kept.
+    fn term_with<'t, K>(&mut self, word: Cow<'t, str>, keep: K) -> Option<Cow<'a, str>>
+    where
+        K: Fn(Cow<'t, str>) -> Cow<'a, str>,
+    {
+        let word = self.filter(word)?;
+        if self.stemmer.is_none() {
+            return Some(keep(word));
+        }
+        let stem = keep(self.stem(word.clone()));
+        if let Some(surface_forms) = &mut self.surface_forms {
+            surface_forms
+                .entry(stem.clone())
+                .or_default()
+                .insert(keep(word));
+        }
+        Some(stem)
+    }
+
+    /// Normalize `word`, dropping it if it is a stop word.
+    fn filter<'t>(&self, word: Cow<'t, str>) -> Option<Cow<'t, str>> {
+        let word = self.normalizer.normalize(word);
+        (!self.stop_keys.contains(&word)).then_some(word)
+    }
+
+    /// Normalize, filter and stem `word`, as for counting,
+    /// but without recording surface forms.
+    pub(crate) fn analyze<'t>(&self, word: Cow<'t, str>) -> Option<Cow<'t, str>> {
+        self.filter(word).map(|word| self.stem(word))
+    }
+
+    fn stem<'t>(&self, word: Cow<'t, str>) -> Cow<'t, str> {
+        match &self.stemmer {
+            Some(stemmer) => stemmer.stem(word),
+            None => word,
+        }
+    }
+
+    fn insert(&mut self, word: Cow<'a, str>) {
+        // This line of code was derived from the example at https://doc.rust-lang.org/std/collections/struct.BTreeMap.html#method.entry
+        self.words
+            .entry(word)
+            .and_modify(|curr| *curr += 1)
+            .or_insert(1);
+    }
+
     /// Report the number of occurrences of the given
     /// `keyword` that are indexed by this BBOW. The keyword
-    /// should be lowercase and not contain punctuation, as
-    /// per the rules of BBOW: otherwise the keyword will
-    /// not match and 0 will be returned.
+    /// is trimmed and normalized just as text is by
+    /// [Bbow::extend_from_text], so `"Stop!"` matches the
+    /// word `stop`. A keyword that is not in the BBOW, or is
+    /// not a word at all, has a count of 0.
+    ///
+    /// If n-grams are being counted, the keyword may be a
+    /// phrase such as `"test string"`. Words added by another
+    /// [Tokenizer], such as `"x1"`, are also found when
+    /// given whole.
     pub fn match_count(&self, keyword: &str) -> usize {
-        self.0[keyword]
+        self.try_match_count(keyword).unwrap_or(0)
+    }
+
+    /// Like [Bbow::match_count], but report an error if the
+    /// `keyword` is not a valid word, or is a phrase longer
+    /// than the longest n-gram being counted, and is not in
+    /// this BBOW as it is.
+    pub fn try_match_count(&self, keyword: &str) -> Result<usize, KeywordError> {
+        let count = self.lookup(keyword, |key| self.words.get(key).copied())?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// The words that were stemmed to the stem of `keyword`,
+    /// in sorted order. This is empty unless surface forms are
+    /// being kept: see [Bbow::keep_surface_forms].
+    pub fn surface_forms(&self, keyword: &str) -> impl Iterator<Item = &str> {
+        self.lookup(keyword, |key| self.surface_forms.as_ref()?.get(key))
+            .ok()
+            .flatten()
+            .into_iter()
+            .flatten()
+            .map(|w| w.as_ref())
+    }
+
+    /// Look `keyword` up with `get` under its key. If that
+    /// finds nothing, look up the whole keyword, normalized
+    /// and stemmed as a single word: a custom [Tokenizer]
+    /// may have counted it that way.
+    pub(crate) fn lookup<T, F>(&self, keyword: &str, get: F) -> Result<Option<T>, KeywordError>
+    where
+        F: Fn(&str) -> Option<T>,
+    {
+        let key = self.key(keyword);
+        if let Ok(Some(found)) = key.as_ref().map(|key| get(key.as_deref()?)) {
+            return Ok(Some(found));
+        }
+        let whole = self.analyze(Cow::Borrowed(keyword));
+        if let Some(found) = whole.and_then(|whole| get(&whole)) {
+            return Ok(Some(found));
+        }
+        key.map(|_| None)
+    }
+
+    /// Find the key under which `keyword` would be counted,
+    /// or `None` if it consists only of stop words.
+    pub(crate) fn key<'k>(&self, keyword: &'k str) -> Result<Option<Cow<'k, str>>, KeywordError> {
+        let tokenizer = WordTokenizer::new();
+        let words: Vec<_> = tokenizer.tokens(keyword).collect();
+        if words.is_empty() {
+            return Err(KeywordError::NotAWord);
+        }
+        let mut terms: Vec<_> = words
+            .into_iter()
+            .filter_map(|word| self.analyze(word))
+            .collect();
+        if terms.len() > *self.ngrams.end() {
+            return Err(KeywordError::MultipleWords(terms.len()));
+        }
+        Ok(match terms.len() {
+            0 => None,
+            1 => terms.pop(),
+            _ => Some(Cow::Owned(join_terms(terms.iter()))),
+        })
+    }
+
+    /// The n-grams of exactly `n` words in this BBOW, in
+    /// sorted order. `ngrams(1)` yields the single words.
+    ///
+    /// Words are counted by the spaces in each key, so a word
+    /// containing a space, from a custom [Tokenizer], is
+    /// listed with the n-grams of its length.
+    pub fn ngrams(&self, n: usize) -> impl Iterator<Item = &str> {
+        self.words
+            .keys()
+            .map(|w| w.as_ref())
+            .filter(move |w: &&str| ngram_order(w) == n)
     }
 
     pub fn words(&'a self) -> impl Iterator<Item = &'a str> {
-        self.0.keys().map(|w| w.as_ref())
+        self.words.keys().map(|w| w.as_ref())
     }
 
     /// Count the overall number of words contained in this BBOW:
     /// multiple occurrences are considered separate.
     pub fn count(&self) -> usize {
-        let mut total = 0;
-        for value in self.0.values() {
-            println!("{}", value);
-            total += value;
-        }
-        total
+        self.words.values().sum()
     }
 
     /// Count the number of unique words contained in this BBOW,
     /// not considering number of occurrences.
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.words.len()
     }
 
     /// Is this BBOW empty?
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.words.is_empty()
+    }
+
+    /// The (at most) `k` words with the largest counts, most
+    /// common first. Words with equal counts are listed in
+    /// sorted order.
+    pub fn most_common(&self, k: usize) -> Vec<(&str, usize)> {
+        bag::most_common(self, k)
+    }
+
+    /// The (at most) `k` words with the smallest counts,
+    /// least common first. Words with equal counts are
+    /// listed in sorted order.
+    pub fn least_common(&self, k: usize) -> Vec<(&str, usize)> {
+        bag::least_common(self, k)
     }
 }
 
@@ -140,6 +600,48 @@ mod tests {
         assert_eq!(5, my_bag.len());
     }
 
+    #[test]
+    fn test_extend_with_custom_tokenizer() {
+        struct Slashes;
+        impl Tokenizer for Slashes {
+            fn tokens<'s, 'a: 's>(
+                &'s self,
+                text: &'a str,
+            ) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+                Box::new(text.split('/').map(Cow::Borrowed))
+            }
+        }
+        let my_bag = Bbow::new().extend_with(&Slashes, "foo/Bar/foo");
+        assert_eq!(2, my_bag.len());
+        assert_eq!(2, my_bag.match_count("foo"));
+        assert_eq!(1, my_bag.match_count("bar"));
+    }
+
+    #[test]
+    fn test_match_count_custom_tokenizer() {
+        struct Lines;
+        impl Tokenizer for Lines {
+            fn tokens<'s, 'a: 's>(
+                &'s self,
+                text: &'a str,
+            ) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+                Box::new(text.lines().map(Cow::Borrowed))
+            }
+        }
+        let my_bag = Bbow::new().extend_with(&Lines, "x1\nNew York\nstop");
+        assert_eq!(1, my_bag.match_count("x1"));
+        assert_eq!(Ok(1), my_bag.try_match_count("new york"));
+        assert_eq!(1, my_bag.match_count("New York"));
+        assert_eq!(1, my_bag.match_count("Stop!"));
+        assert!(my_bag.ngrams(1).eq(["stop", "x1"]));
+        assert!(my_bag.ngrams(2).eq(["new york"]));
+        assert_eq!(Err(KeywordError::NotAWord), my_bag.try_match_count("x2"));
+        assert_eq!(
+            Err(KeywordError::MultipleWords(2)),
+            my_bag.try_match_count("old york")
+        );
+    }
+
     #[test]
     fn test_match_count() {
         let mut my_bag = Bbow::new();
@@ -147,6 +649,36 @@ mod tests {
         assert_eq!(3, my_bag.match_count("b"));
     }
 
+    #[test]
+    fn test_match_count_normalizes_keyword() {
+        let my_bag = Bbow::new().extend_from_text("Can't stop this! Stop!");
+        assert_eq!(2, my_bag.match_count("Stop!"));
+        assert_eq!(2, my_bag.match_count("“STOP”"));
+    }
+
+    #[test]
+    fn test_match_count_missing() {
+        let my_bag = Bbow::new().extend_from_text("b b b-banana b");
+        assert_eq!(0, my_bag.match_count("banana"));
+        assert_eq!(0, my_bag.match_count("b-banana"));
+    }
+
+    #[test]
+    fn test_try_match_count() {
+        let my_bag = Bbow::new().extend_from_text("b b b-banana b");
+        assert_eq!(Ok(3), my_bag.try_match_count("B."));
+        assert_eq!(Ok(0), my_bag.try_match_count("banana"));
+        assert_eq!(
+            Err(KeywordError::NotAWord),
+            my_bag.try_match_count("b-banana")
+        );
+        assert_eq!(Err(KeywordError::NotAWord), my_bag.try_match_count(""));
+        assert_eq!(
+            Err(KeywordError::MultipleWords(2)),
+            my_bag.try_match_count("b b")
+        );
+    }
+
     #[test]
     fn test_len() {
         let mut my_bag = Bbow::new();
@@ -154,6 +686,204 @@ mod tests {
         assert_eq!(2, my_bag.len());
     }
 
+    #[test]
+    fn test_doc_example() {
+        let bbow = Bbow::new().extend_from_text("It ain't over untïl it ain't, over.");
+        assert_eq!(3, bbow.len());
+        assert_eq!(2, bbow.match_count("it"));
+        assert_eq!(2, bbow.match_count("over"));
+        assert_eq!(1, bbow.match_count("untïl"));
+    }
+
+    #[test]
+    fn test_normalizer() {
+        let normalizer = Normalizer::new()
+            .case(CaseMode::Fold)
+            .form(Some(NormalForm::Nfc));
+        let bbow = Bbow::new()
+            .with_normalizer(normalizer)
+            .extend_from_text("untïl unti\u{308}l Straße STRASSE");
+        assert_eq!(2, bbow.len());
+        assert_eq!(2, bbow.match_count("untïl"));
+        assert_eq!(2, bbow.match_count("unti\u{308}l"));
+        assert_eq!(2, bbow.match_count("STRAßE"));
+    }
+
+    #[test]
+    fn test_with_stop_words() {
+        let bbow = Bbow::new()
+            .with_stop_words(StopWords::language(Language::English))
+            .extend_from_text("This is a test string for test purposes");
+        assert_eq!(3, bbow.len());
+        assert_eq!(4, bbow.count());
+        assert_eq!(0, bbow.match_count("this"));
+    }
+
+    #[test]
+    fn test_stop_words_are_normalized() {
+        let bbow = Bbow::new()
+            .with_stop_words(["STRASSE"].into_iter().collect())
+            .with_normalizer(Normalizer::new().case(CaseMode::Fold))
+            .extend_from_text("Straße Weg");
+        assert_eq!(1, bbow.len());
+        assert_eq!(1, bbow.match_count("weg"));
+    }
+
+    #[test]
+    fn test_stop_words_builder_order() {
+        let preserve = || Normalizer::new().case(CaseMode::Preserve);
+        let stop_words = || ["The"].into_iter().collect::<StopWords>();
+        let before = Bbow::new()
+            .with_stop_words(stop_words())
+            .with_normalizer(preserve())
+            .extend_from_text("The the");
+        let after = Bbow::new()
+            .with_normalizer(preserve())
+            .with_stop_words(stop_words())
+            .extend_from_text("The the");
+        assert!(before.iter().eq(after.iter()));
+        assert!(before.iter().eq([("the", 1)]));
+    }
+
+    #[test]
+    fn test_remove_stop_words() {
+        let bbow = Bbow::new()
+            .extend_from_text("This is a test string for test purposes")
+            .remove_stop_words(&StopWords::language(Language::English));
+        assert_eq!(3, bbow.len());
+        assert_eq!(2, bbow.match_count("test"));
+    }
+
+    #[test]
+    fn test_remove_stop_words_ngrams() {
+        let bbow = Bbow::new()
+            .with_ngrams(1..=2)
+            .extend_from_text("the cat sat")
+            .remove_stop_words(&["the"].into_iter().collect());
+        assert!(bbow.iter().eq([("cat", 1), ("cat sat", 1), ("sat", 1)]));
+    }
+
+    #[test]
+    fn test_remove_stop_words_stemmed() {
+        let text = "Tests of this stop";
+        let stop_words = StopWords::language(Language::English);
+        let removed = Bbow::new()
+            .with_stemmer(TrimS)
+            .keep_surface_forms(true)
+            .extend_from_text(text)
+            .remove_stop_words(&stop_words);
+        let filtered = Bbow::new()
+            .with_stemmer(TrimS)
+            .with_stop_words(stop_words)
+            .extend_from_text(text);
+        assert!(removed.iter().eq(filtered.iter()));
+        assert_eq!(0, removed.surface_forms("this").count());
+        assert!(removed.surface_forms("tests").eq(["tests"]));
+    }
+
+    #[derive(Debug)]
+    struct TrimS;
+
+    impl Stemmer for TrimS {
+        fn stem<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+            match word {
+                Cow::Borrowed(w) => Cow::Borrowed(w.trim_end_matches('s')),
+                Cow::Owned(w) => Cow::Owned(w.trim_end_matches('s').to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_stemmer() {
+        let bbow = Bbow::new()
+            .with_stemmer(TrimS)
+            .extend_from_text("Tests test tests");
+        assert_eq!(1, bbow.len());
+        assert_eq!(3, bbow.match_count("tests"));
+        assert_eq!(0, bbow.surface_forms("test").count());
+    }
+
+    #[test]
+    fn test_surface_forms() {
+        let bbow = Bbow::new()
+            .with_stemmer(TrimS)
+            .keep_surface_forms(true)
+            .extend_from_text("Tests test tests");
+        let forms: Vec<_> = bbow.surface_forms("test").collect();
+        assert_eq!(vec!["test", "tests"], forms);
+    }
+
+    #[cfg(feature = "stemming")]
+    #[test]
+    fn test_snowball_stemmer() {
+        let bbow = Bbow::new()
+            .with_stemmer(SnowballStemmer::new(Language::English))
+            .extend_from_text("This is a te'st string test string test string testing tests");
+        assert_eq!(4, bbow.match_count("test"));
+        assert_eq!(4, bbow.match_count("testing"));
+    }
+
+    #[cfg(feature = "stemming")]
+    #[test]
+    fn test_snowball_remove_stop_words() {
+        let bbow = Bbow::new()
+            .with_stemmer(SnowballStemmer::new(Language::English))
+            .extend_from_text("tests during testing")
+            .remove_stop_words(&StopWords::language(Language::English));
+        assert!(bbow.iter().eq([("test", 2)]));
+    }
+
+    #[test]
+    fn test_ngrams() {
+        let bbow = Bbow::new()
+            .with_ngrams(1..=3)
+            .extend_from_text("This is a test string test string");
+        assert_eq!(2, bbow.match_count("test string"));
+        assert_eq!(1, bbow.match_count("String, test"));
+        assert_eq!(1, bbow.match_count("a test string"));
+        assert_eq!(2, bbow.match_count("test"));
+        assert_eq!(0, bbow.match_count("this test"));
+        assert_eq!(5, bbow.ngrams(1).count());
+        assert_eq!(5, bbow.ngrams(2).count());
+        assert_eq!(5, bbow.ngrams(3).count());
+        let bigrams: Vec<_> = bbow.ngrams(2).collect();
+        assert_eq!(
+            vec!["a test", "is a", "string test", "test string", "this is"],
+            bigrams
+        );
+    }
+
+    #[test]
+    fn test_ngrams_only_bigrams() {
+        let bbow = Bbow::new()
+            .with_ngrams(2..=2)
+            .extend_from_text("one two")
+            .extend_from_text("three");
+        let keys: Vec<_> = bbow.words().collect();
+        assert_eq!(vec!["one two"], keys);
+        assert_eq!(
+            Err(KeywordError::MultipleWords(3)),
+            bbow.try_match_count("one two three")
+        );
+    }
+
+    #[test]
+    fn test_ngrams_skip_stop_words() {
+        let bbow = Bbow::new()
+            .with_stop_words(StopWords::language(Language::English))
+            .with_ngrams(1..=2)
+            .extend_from_text("Stop this test");
+        assert_eq!(1, bbow.match_count("stop test"));
+        assert_eq!(1, bbow.match_count("stop this test"));
+        assert_eq!(Ok(0), bbow.try_match_count("this"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ngrams_zero() {
+        let _ = Bbow::new().with_ngrams(0..=2);
+    }
+
     #[test]
     fn test_count() {
         let mut bbow = Bbow::new();
@@ -162,15 +892,32 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_empty() {
         let bbow = Bbow::new();
         assert_eq!(true, bbow.is_empty());
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_empty_with_words() {
         let mut bbow = Bbow::new();
         bbow = bbow.extend_from_text("Now there is something in here");
         assert_eq!(false, bbow.is_empty());
     }
+
+    #[test]
+    fn test_most_common() {
+        let bbow = Bbow::new().extend_from_text("c b a b c c d d e");
+        assert_eq!(vec![("c", 3), ("b", 2), ("d", 2)], bbow.most_common(3));
+        assert_eq!(5, bbow.most_common(10).len());
+        assert!(bbow.most_common(0).is_empty());
+    }
+
+    #[test]
+    fn test_least_common() {
+        let bbow = Bbow::new().extend_from_text("c b a b c c d d e");
+        assert_eq!(vec![("a", 1), ("e", 1), ("b", 2)], bbow.least_common(3));
+        assert!(Bbow::new().least_common(3).is_empty());
+    }
 }
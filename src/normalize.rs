@@ -0,0 +1,193 @@
+//! Normalizing words before they are counted.
+//!
+//! The same word can be spelled with different code points:
+//! `untïl` may be written with a precomposed `ï` or with an
+//! `i` followed by a combining diaeresis, and `Straße` may be
+//! written `STRASSE`. A [Normalizer] maps such spellings to a
+//! single key, so that they are counted together.
+
+use std::borrow::Cow;
+
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_normalization::{is_nfc, is_nfkc, UnicodeNormalization};
+
+/// How a [Normalizer] treats letter case.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    /// Leave case alone.
+    Preserve,
+    /// Map uppercase letters to lowercase. This is the
+    /// default, and the classic behavior of a BBOW.
+    #[default]
+    Lower,
+    /// Full Unicode case folding: in addition to lowercasing,
+    /// letters such as `ß` fold to their multi-character
+    /// equivalents, so `Straße` and `STRASSE` match.
+    Fold,
+}
+
+/// A Unicode normalization form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalForm {
+    /// Canonical composition.
+    Nfc,
+    /// Compatibility composition: in addition to NFC,
+    /// ligatures, width variants and the like are replaced
+    /// by their plain equivalents.
+    Nfkc,
+}
+
+/// Settings for mapping a word to its key in a BBOW.
+///
+/// The default normalizer only lowercases, which matches
+/// the behavior described in the crate documentation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Normalizer {
+    case: CaseMode,
+    form: Option<NormalForm>,
+    strip_diacritics: bool,
+}
+
+impl Normalizer {
+    /// Make a new normalizer with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the treatment of letter case.
+    pub fn case(mut self, case: CaseMode) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Bring each word into the given normalization form, or
+    /// leave it as-is for `None`.
+    pub fn form(mut self, form: Option<NormalForm>) -> Self {
+        self.form = form;
+        self
+    }
+
+    /// Remove combining diacritical marks, so that `untïl`
+    /// becomes `until`.
+    pub fn strip_diacritics(mut self, strip: bool) -> Self {
+        self.strip_diacritics = strip;
+        self
+    }
+
+    /// Normalize `word`. The word is returned unchanged,
+    /// without copying, when there is nothing to do.
+    pub fn normalize<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+        let word = self.apply_form(word);
+        let word = if self.strip_diacritics {
+            strip_diacritics(word)
+        } else {
+            word
+        };
+        match self.case {
+            CaseMode::Preserve => word,
+            CaseMode::Lower if has_uppercase(&word) => Cow::Owned(word.to_lowercase()),
+            CaseMode::Lower => word,
+            CaseMode::Fold => match case_fold(&word) {
+                // Folding can leave a word out of normal form.
+                Some(folded) => self.apply_form(Cow::Owned(folded)),
+                None => word,
+            },
+        }
+    }
+
+    fn apply_form<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+        match self.form {
+            Some(NormalForm::Nfc) if !is_nfc(&word) => Cow::Owned(word.nfc().collect()),
+            Some(NormalForm::Nfkc) if !is_nfkc(&word) => Cow::Owned(word.nfkc().collect()),
+            _ => word,
+        }
+    }
+}
+
+pub(crate) fn has_uppercase(word: &str) -> bool {
+    word.chars().any(char::is_uppercase)
+}
+
+/// Case-fold `word`, or return `None` if folding would not
+/// change it.
+///
+/// The standard library has no case folding, so this is
+/// approximated by lowercasing, uppercasing and lowercasing
+/// again: the round trip through uppercase is what turns `ß`
+/// into `ss`.
+fn case_fold(word: &str) -> Option<String> {
+    if word.is_ascii() {
+        return has_uppercase(word).then(|| word.to_ascii_lowercase());
+    }
+    let folded = word.to_lowercase().to_uppercase().to_lowercase();
+    (folded != word).then_some(folded)
+}
+
+fn strip_diacritics(word: Cow<'_, str>) -> Cow<'_, str> {
+    if word.is_ascii() {
+        return word;
+    }
+    let stripped: String = word
+        .nfd()
+        .filter(|&c| get_general_category(c) != GeneralCategory::NonspacingMark)
+        .nfc()
+        .collect();
+    if stripped == word {
+        word
+    } else {
+        Cow::Owned(stripped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize<'a>(normalizer: &Normalizer, word: &'a str) -> Cow<'a, str> {
+        normalizer.normalize(Cow::Borrowed(word))
+    }
+
+    #[test]
+    fn test_default_lowercases() {
+        let normalizer = Normalizer::new();
+        assert_eq!("stop", normalize(&normalizer, "Stop"));
+        assert_eq!("straße", normalize(&normalizer, "Straße"));
+        assert!(matches!(normalize(&normalizer, "stop"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_case_fold() {
+        let normalizer = Normalizer::new().case(CaseMode::Fold);
+        assert_eq!("strasse", normalize(&normalizer, "Straße"));
+        assert_eq!("strasse", normalize(&normalizer, "STRASSE"));
+        assert_eq!("strasse", normalize(&normalizer, "STRAẞE"));
+    }
+
+    #[test]
+    fn test_preserve_case() {
+        let normalizer = Normalizer::new().case(CaseMode::Preserve);
+        assert_eq!("Stop", normalize(&normalizer, "Stop"));
+    }
+
+    #[test]
+    fn test_nfc() {
+        let normalizer = Normalizer::new().form(Some(NormalForm::Nfc));
+        assert_eq!("untïl", normalize(&normalizer, "unti\u{308}l"));
+        assert!(matches!(normalize(&normalizer, "untïl"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_nfkc() {
+        let normalizer = Normalizer::new().form(Some(NormalForm::Nfkc));
+        assert_eq!("find", normalize(&normalizer, "ﬁnd"));
+        assert_eq!("ﬁnd", normalize(&Normalizer::new(), "ﬁnd"));
+    }
+
+    #[test]
+    fn test_strip_diacritics() {
+        let normalizer = Normalizer::new().strip_diacritics(true);
+        assert_eq!("until", normalize(&normalizer, "untïl"));
+        assert_eq!("until", normalize(&normalizer, "unti\u{308}l"));
+        assert_eq!("cafe", normalize(&normalizer, "Café"));
+    }
+}
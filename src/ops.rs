@@ -0,0 +1,135 @@
+//! Multiset operations between bags.
+//!
+//! A [Bbow] is a multiset of words, so bags can be combined
+//! without re-reading their texts. The result keeps the
+//! settings (normalizer, stop words and so on) of the
+//! left-hand bag, and reuses the keys of both bags, so
+//! borrowed keys stay zero-copy.
+
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::{bag, Bbow, SurfaceForms};
+
+impl<'a> Bbow<'a> {
+    /// Combine with `other`, keeping the larger count of each
+    /// word: the multiset union.
+    pub fn union(mut self, other: Bbow<'a>) -> Self {
+        bag::union(&mut self, other);
+        self
+    }
+
+    /// Keep only words also in `other`, with the smaller of
+    /// the two counts: the multiset intersection.
+    pub fn intersection(mut self, other: &Bbow<'_>) -> Self {
+        bag::intersection(&mut self, other);
+        self
+    }
+
+    /// Merge surface forms recorded by another bag into
+    /// this one, if this bag is recording them.
+    pub(crate) fn merge_surface_forms(&mut self, other: Option<SurfaceForms<'a>>) {
+        if let (Some(mine), Some(theirs)) = (&mut self.surface_forms, other) {
+            for (stem, forms) in theirs {
+                mine.entry(stem).or_default().extend(forms);
+            }
+        }
+    }
+}
+
+/// The sum of two bags: counts are added.
+impl<'a> Add for Bbow<'a> {
+    type Output = Bbow<'a>;
+
+    fn add(mut self, other: Bbow<'a>) -> Self::Output {
+        self += other;
+        self
+    }
+}
+
+impl<'a> AddAssign for Bbow<'a> {
+    fn add_assign(&mut self, other: Bbow<'a>) {
+        bag::add(self, other);
+    }
+}
+
+impl<'a> AddAssign<&Bbow<'a>> for Bbow<'a> {
+    fn add_assign(&mut self, other: &Bbow<'a>) {
+        bag::add_ref(self, other);
+    }
+}
+
+/// The difference of two bags: counts are subtracted, and
+/// words whose count would drop to zero or below are
+/// removed.
+impl<'a> Sub<&Bbow<'_>> for Bbow<'a> {
+    type Output = Bbow<'a>;
+
+    fn sub(mut self, other: &Bbow<'_>) -> Self::Output {
+        self -= other;
+        self
+    }
+}
+
+impl<'a> Sub for Bbow<'a> {
+    type Output = Bbow<'a>;
+
+    fn sub(self, other: Bbow<'a>) -> Self::Output {
+        self - &other
+    }
+}
+
+impl SubAssign<&Bbow<'_>> for Bbow<'_> {
+    fn sub_assign(&mut self, other: &Bbow<'_>) {
+        bag::subtract(self, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{bag, pairs};
+
+    fn expected(counts: &[(&str, usize)]) -> Vec<(String, usize)> {
+        counts.iter().map(|&(w, c)| (w.to_string(), c)).collect()
+    }
+
+    #[test]
+    fn test_add() {
+        let sum = bag("a a b") + bag("b c");
+        assert_eq!(expected(&[("a", 2), ("b", 2), ("c", 1)]), pairs(&sum));
+    }
+
+    #[test]
+    fn test_add_assign_ref() {
+        let mut sum = bag("a a b");
+        let other = bag("b c");
+        sum += &other;
+        sum += &other;
+        assert_eq!(expected(&[("a", 2), ("b", 3), ("c", 2)]), pairs(&sum));
+    }
+
+    #[test]
+    fn test_union() {
+        let union = bag("a a b").union(bag("a b b b c"));
+        assert_eq!(expected(&[("a", 2), ("b", 3), ("c", 1)]), pairs(&union));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let intersection = bag("a a b d").intersection(&bag("a b b b c"));
+        assert_eq!(expected(&[("a", 1), ("b", 1)]), pairs(&intersection));
+    }
+
+    #[test]
+    fn test_sub() {
+        let difference = bag("a a a b c") - bag("a b b d");
+        assert_eq!(expected(&[("a", 2), ("c", 1)]), pairs(&difference));
+    }
+
+    #[test]
+    fn test_sum_matches_concatenation() {
+        let sum = bag("This is a test") + bag("a TEST string");
+        let whole = bag("This is a test a TEST string");
+        assert_eq!(pairs(&whole), pairs(&sum));
+        assert_eq!(whole.count(), sum.count());
+    }
+}
@@ -0,0 +1,149 @@
+//! Bags that own their words.
+//!
+//! A [Bbow] borrows its words from the text it was built
+//! from whenever it can, so it cannot outlive that text.
+//! [Bbow::into_owned] copies the borrowed words, giving a
+//! `Bbow<'static>` that can be cached or sent to another
+//! thread. [OwnedBbow] wraps such a bag, and can keep
+//! counting words from texts that do not live as long as
+//! it does.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use crate::{Bbow, Tokenizer, WordTokenizer};
+
+/// Copy `word` if it is borrowed.
+pub(crate) fn owned(word: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(word.into_owned())
+}
+
+impl Bbow<'_> {
+    /// Copy any words borrowed from the source text, so that
+    /// this BBOW no longer depends on it.
+    pub fn into_owned(self) -> Bbow<'static> {
+        Bbow {
+            words: self
+                .words
+                .into_iter()
+                .map(|(word, count)| (owned(word), count))
+                .collect(),
+            surface_forms: self.surface_forms.map(|surface_forms| {
+                surface_forms
+                    .into_iter()
+                    .map(|(stem, forms)| (owned(stem), forms.into_iter().map(owned).collect()))
+                    .collect()
+            }),
+            normalizer: self.normalizer,
+            stop_words: self.stop_words,
+            stop_keys: self.stop_keys,
+            stemmer: self.stemmer,
+            ngrams: self.ngrams,
+        }
+    }
+}
+
+/// A [Bbow] that owns all of its words.
+///
+/// All the read-only methods of [Bbow] are available through
+/// [Deref]. Text is added with [OwnedBbow::extend_from_text],
+/// which, unlike [Bbow::extend_from_text], copies the words
+/// it keeps and so accepts text of any lifetime.
+#[derive(Debug, Clone, Default)]
+pub struct OwnedBbow(pub(crate) Bbow<'static>);
+
+impl OwnedBbow {
+    /// Make a new empty owned BBOW.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse each word in the target text and record it in
+    /// this BBOW, with the same settings and rules as
+    /// [Bbow::extend_from_text].
+    pub fn extend_from_text(self, text: &str) -> Self {
+        self.extend_with(&WordTokenizer::new(), text)
+    }
+
+    /// Like [OwnedBbow::extend_from_text], but split `text`
+    /// into words with `tokenizer`.
+    pub fn extend_with<T: Tokenizer + ?Sized>(mut self, tokenizer: &T, text: &str) -> Self {
+        let mut window = VecDeque::new();
+        self.0
+            .count_words_with(tokenizer.tokens(text), &mut window, owned);
+        self
+    }
+
+    /// The bag wrapped by this owned BBOW.
+    pub fn into_inner(self) -> Bbow<'static> {
+        self.0
+    }
+}
+
+impl Deref for OwnedBbow {
+    type Target = Bbow<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Bbow<'_>> for OwnedBbow {
+    fn from(bag: Bbow<'_>) -> Self {
+        OwnedBbow(bag.into_owned())
+    }
+}
+
+impl From<OwnedBbow> for Bbow<'static> {
+    fn from(bag: OwnedBbow) -> Self {
+        bag.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_owned() {
+        let text = String::from("Can't stop this! Stop!");
+        let bag = Bbow::new().extend_from_text(&text).into_owned();
+        drop(text);
+        assert_eq!(2, bag.match_count("stop"));
+        assert!(bag.iter().eq(vec![("stop", 2), ("this", 1)]));
+    }
+
+    #[test]
+    fn test_owned_bbow() {
+        let mut bag = OwnedBbow::from(Bbow::new().with_ngrams(1..=2));
+        for line in ["Stop this", "this test"] {
+            let line = line.to_string();
+            bag = bag.extend_from_text(&line);
+        }
+        assert_eq!(2, bag.match_count("this"));
+        assert_eq!(1, bag.match_count("stop this"));
+        assert_eq!(0, bag.match_count("this this"));
+        assert_eq!(5, bag.len());
+    }
+
+    #[test]
+    fn test_send_across_threads() {
+        let text = String::from("It ain't over untïl it ain't, over.");
+        let bag = OwnedBbow::from(Bbow::new().extend_from_text(&text));
+        drop(text);
+        let count = std::thread::spawn(move || bag.match_count("over"))
+            .join()
+            .unwrap();
+        assert_eq!(2, count);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let bag = OwnedBbow::new().extend_from_text("stop this");
+        let bag: Bbow<'static> = bag.into();
+        let bag = OwnedBbow::from(bag + Bbow::new().extend_from_text("stop"));
+        assert_eq!(2, bag.match_count("stop"));
+        assert_eq!(3, bag.into_inner().count());
+    }
+}
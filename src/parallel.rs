@@ -0,0 +1,252 @@
+//! Counting the words of a large text on several threads.
+//!
+//! The text is cut into shards at whitespace, so that no
+//! word is split between two shards. Each shard is counted
+//! into its own bag on its own thread, and the bags are then
+//! added together. When n-grams are counted, the n-grams
+//! spanning two or more shards are counted afterwards from
+//! the first and last few terms of each shard, so the result
+//! is the same as counting the whole text on one thread.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::bag::{self, Bag};
+use crate::{for_each_ngram, join_terms, Bbow, Tokenizer, WordTokenizer};
+
+/// A bag that can be counted a shard at a time: [Bbow] and
+/// [HashBbow](crate::HashBbow).
+pub(crate) trait ShardBag<'a>: Bag<'a> + Sized + Send {
+    /// A new empty bag with the same settings.
+    fn empty(&self) -> Self;
+}
+
+impl<'a> ShardBag<'a> for Bbow<'a> {
+    fn empty(&self) -> Self {
+        self.empty_clone()
+    }
+}
+
+/// The counts of one shard, with the terms needed to count
+/// the n-grams spanning its edges.
+struct Shard<'a, B> {
+    bag: B,
+    /// The first terms of the shard, at most one fewer than
+    /// the longest n-gram.
+    head: Vec<Cow<'a, str>>,
+    /// The last terms of the shard, at most as many as the
+    /// longest n-gram.
+    tail: VecDeque<Cow<'a, str>>,
+}
+
+/// Cut `text` into at most `count` pieces of about the same
+/// length, each but the last ending just after whitespace.
+pub(crate) fn shards(text: &str, count: usize) -> Vec<&str> {
+    let count = count.min(text.len()).max(1);
+    let size = text.len().div_ceil(count);
+    let mut shards = Vec::with_capacity(count);
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.len() <= size {
+            shards.push(rest);
+            break;
+        }
+        let mut start = size;
+        while !rest.is_char_boundary(start) {
+            start += 1;
+        }
+        let end = rest[start..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(rest.len(), |(i, c)| start + i + c.len_utf8());
+        let (shard, after) = rest.split_at(end);
+        shards.push(shard);
+        rest = after;
+    }
+    shards
+}
+
+/// Count the words of `target` into `bag` on up to
+/// `threads` threads, as [Bbow::par_extend_with] does. No
+/// more threads are used than can run in parallel.
+pub(crate) fn par_extend<'a, B, T>(bag: &mut B, tokenizer: &T, target: &'a str, threads: usize)
+where
+    B: ShardBag<'a>,
+    T: Tokenizer + Sync + ?Sized,
+{
+    let available = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    extend_shards(bag, tokenizer, shards(target, threads.min(available)));
+}
+
+/// Count the words of `shards`, consecutive pieces of a text
+/// cut at whitespace, into `bag`, each on its own thread.
+pub(crate) fn extend_shards<'a, B, T>(bag: &mut B, tokenizer: &T, shards: Vec<&'a str>)
+where
+    B: ShardBag<'a>,
+    T: Tokenizer + Sync + ?Sized,
+{
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = shards
+            .into_iter()
+            .map(|shard| {
+                let empty = bag.empty();
+                scope.spawn(move || count_shard(empty, tokenizer, shard))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("counting thread panicked"))
+            .collect()
+    });
+
+    // The last terms of the text so far, at most one fewer
+    // than the longest n-gram.
+    let longest = *bag.settings().ngrams.end();
+    let mut window = VecDeque::with_capacity(longest);
+    for shard in results {
+        bag::add(bag, shard.bag);
+        if longest == 1 {
+            continue;
+        }
+        count_spanning(bag, &window, &shard.head);
+        window.extend(shard.tail);
+        while window.len() >= longest {
+            window.pop_front();
+        }
+    }
+}
+
+/// Count the words of `shard` into the (empty) `bag`.
+fn count_shard<'a, B, T>(mut bag: B, tokenizer: &T, shard: &'a str) -> Shard<'a, B>
+where
+    B: ShardBag<'a>,
+    T: Tokenizer + ?Sized,
+{
+    let ngrams = bag.settings().ngrams.clone();
+    let head_length = *ngrams.end() - 1;
+    let mut head = Vec::with_capacity(head_length);
+    let mut tail = VecDeque::with_capacity(head_length + 1);
+    for word in tokenizer.tokens(shard) {
+        let Some(term) = bag.settings_mut().term(word) else {
+            continue;
+        };
+        if head.len() < head_length {
+            head.push(term.clone());
+        }
+        for_each_ngram(&ngrams, term, &mut tail, |key| bag.insert(key));
+    }
+    Shard { bag, head, tail }
+}
+
+/// Count into `bag` the n-grams that start in `before` and
+/// end in `head`, the terms just after it.
+fn count_spanning<'a, B>(bag: &mut B, before: &VecDeque<Cow<'a, str>>, head: &[Cow<'a, str>])
+where
+    B: ShardBag<'a>,
+{
+    let ngrams = bag.settings().ngrams.clone();
+    let (min_n, max_n) = (*ngrams.start(), *ngrams.end());
+    for end in 1..=head.len() {
+        for start in 1..=before.len() {
+            let n = start + end;
+            if n < min_n || n > max_n {
+                continue;
+            }
+            let terms = before.range(before.len() - start..).chain(&head[..end]);
+            bag.insert(Cow::Owned(join_terms(terms)));
+        }
+    }
+}
+
+impl<'a> Bbow<'a> {
+    /// Parse the `target` text and add its words to this
+    /// BBOW, like [Bbow::extend_from_text], but counting
+    /// pieces of the text on up to `threads` threads at once.
+    /// No more threads are used than the machine can run in
+    /// parallel. The result is the same as that of
+    /// [Bbow::extend_from_text].
+    ///
+    /// This only pays off for long texts: each thread must
+    /// have many words to count.
+    pub fn par_extend_from_text(self, target: &'a str, threads: usize) -> Self {
+        self.par_extend_with(&WordTokenizer::new(), target, threads)
+    }
+
+    /// Like [Bbow::par_extend_from_text], but split the text
+    /// into words with `tokenizer`. The tokenizer should not
+    /// join words across whitespace.
+    pub fn par_extend_with<T>(mut self, tokenizer: &T, target: &'a str, threads: usize) -> Self
+    where
+        T: Tokenizer + Sync + ?Sized,
+    {
+        if threads <= 1 {
+            return self.extend_with(tokenizer, target);
+        }
+        par_extend(&mut self, tokenizer, target, threads);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::pairs;
+    use crate::{Language, StopWords};
+
+    const TEXT: &str = "It ain't over untïl it ain't, over. Can't stop this! Stop! \
+                        This is a test string for test purposes.";
+
+    #[test]
+    fn test_shards() {
+        let text = "one two  three four";
+        assert_eq!(vec!["one two  three ", "four"], shards(text, 2));
+        assert_eq!(vec!["one ", "two ", " three ", "four"], shards(text, 8));
+        assert_eq!(vec![text], shards(text, 1));
+        assert!(shards("", 4).is_empty());
+        for count in 1..=text.len() + 1 {
+            assert_eq!(text, shards(text, count).concat());
+        }
+        assert_eq!(text, shards(text, usize::MAX).concat());
+    }
+
+    #[test]
+    fn test_same_as_sequential() {
+        let templates = [
+            Bbow::new(),
+            Bbow::new().with_ngrams(1..=3),
+            Bbow::new().with_ngrams(2..=4),
+            Bbow::new()
+                .with_ngrams(1..=2)
+                .with_stop_words(StopWords::language(Language::English)),
+        ];
+        for template in templates {
+            let expected = template.clone().extend_from_text(TEXT);
+            for count in 1..=24 {
+                let mut bag = template.clone();
+                extend_shards(&mut bag, &WordTokenizer::new(), shards(TEXT, count));
+                assert_eq!(pairs(&expected), pairs(&bag), "{} shards", count);
+            }
+            let bag = template.clone().par_extend_from_text(TEXT, 4);
+            assert_eq!(pairs(&expected), pairs(&bag));
+        }
+    }
+
+    #[test]
+    fn test_huge_thread_count() {
+        let bag = Bbow::new().par_extend_from_text("a b c d", usize::MAX);
+        assert_eq!(pairs(&Bbow::new().extend_from_text("a b c d")), pairs(&bag));
+    }
+
+    #[test]
+    fn test_extends_existing() {
+        let bag = Bbow::new()
+            .with_ngrams(1..=2)
+            .extend_from_text("stop")
+            .par_extend_from_text("this test", 4);
+        assert_eq!(1, bag.match_count("this test"));
+        assert_eq!(0, bag.match_count("stop this"));
+        assert!(matches!(bag.words.keys().next(), Some(Cow::Borrowed(_))));
+    }
+}
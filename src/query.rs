@@ -0,0 +1,673 @@
+//! A small query language for searching an
+//! [InvertedIndex] or testing a [Bbow].
+//!
+//! A query is made of:
+//!
+//! * words, such as `test`, matching that word;
+//! * prefixes, such as `test*`, matching any word starting
+//!   with `test`;
+//! * fuzzy words, such as `word~1`, matching any word within
+//!   the given number of single-character insertions,
+//!   deletions and substitutions (2 if the number is left
+//!   off);
+//! * phrases, such as `"test string"`, matching the words
+//!   in order. Each part of a phrase must be a word, as a
+//!   word on its own must;
+//! * the operators `NOT`, `AND` and `OR`, binding in that
+//!   order from tightest to loosest, and parentheses for
+//!   grouping. Operators must be uppercase: `and` is a word.
+//!
+//! Two queries next to each other with no operator between
+//! them are joined by `AND`, so `stop AND NOT this` can also
+//! be written `stop NOT this`.
+//!
+//! Words and phrases are trimmed and normalized just as text
+//! is by [Bbow::extend_from_text]. Prefixes are normalized
+//! but not stemmed, and are matched against the stemmed
+//! words of the index: with a stemmer, `tests*` does not
+//! match a document containing `tests`, which is indexed as
+//! `test`.
+//!
+//! A query may nest parentheses and `NOT`s at most
+//! 64 deep, and contain at most 1024 words and phrases.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::index::{difference, intersect, union};
+use crate::{Bbow, InvertedIndex, Tokenizer, WordTokenizer};
+
+/// Edit distance of a fuzzy word with no explicit distance.
+const DEFAULT_DISTANCE: usize = 2;
+
+/// Deepest nesting of parentheses and `NOT`s in a query.
+const MAX_DEPTH: usize = 64;
+
+/// Most words and phrases in a query.
+const MAX_TERMS: usize = 1024;
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// A single word: `test`.
+    Term(String),
+    /// Words starting with a prefix: `test*`. The prefix is
+    /// matched against stems, not the words of the text.
+    Prefix(String),
+    /// Words within an edit distance of a word: `word~1`.
+    Fuzzy(String, usize),
+    /// Words in order: `"test string"`.
+    Phrase(String),
+    /// Both queries match: `a AND b`.
+    And(Box<Query>, Box<Query>),
+    /// Either query matches: `a OR b`.
+    Or(Box<Query>, Box<Query>),
+    /// The query does not match: `NOT a`.
+    Not(Box<Query>),
+}
+
+/// Why a query could not be parsed. Positions are byte
+/// offsets into the query text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The query ended where a word, phrase or parenthesized
+    /// query was expected, as after `AND` or in an empty
+    /// query.
+    UnexpectedEnd,
+    /// An operator or parenthesis appeared where it cannot.
+    Unexpected { position: usize, found: String },
+    /// A `"` has no closing `"`.
+    UnclosedQuote { position: usize },
+    /// A `(` has no closing `)`.
+    UnclosedParen { position: usize },
+    /// A search word is not a valid word.
+    NotAWord { position: usize, text: String },
+    /// A phrase contains no words.
+    EmptyPhrase { position: usize },
+    /// The distance after `~` is not a number.
+    InvalidDistance { position: usize, text: String },
+    /// A `(` or `NOT` is nested too deeply.
+    TooDeep { position: usize },
+    /// The query has too many words and phrases: this is the
+    /// first one over the limit.
+    TooManyTerms { position: usize },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnexpectedEnd => write!(f, "unexpected end of query"),
+            QueryError::Unexpected { position, found } => {
+                write!(f, "unexpected {:?} at {}", found, position)
+            }
+            QueryError::UnclosedQuote { position } => {
+                write!(f, "unclosed quote at {}", position)
+            }
+            QueryError::UnclosedParen { position } => {
+                write!(f, "unclosed parenthesis at {}", position)
+            }
+            QueryError::NotAWord { position, text } => {
+                write!(f, "{:?} at {} is not a word", text, position)
+            }
+            QueryError::EmptyPhrase { position } => {
+                write!(f, "phrase at {} contains no words", position)
+            }
+            QueryError::InvalidDistance { position, text } => {
+                write!(f, "invalid edit distance {:?} at {}", text, position)
+            }
+            QueryError::TooDeep { position } => {
+                write!(f, "query nested too deeply at {}", position)
+            }
+            QueryError::TooManyTerms { position } => {
+                write!(f, "too many words in query at {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A lexical element of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Lexeme<'q> {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Phrase(&'q str),
+    Word(&'q str),
+}
+
+/// Split `text` into lexemes with their positions.
+fn lex(text: &str) -> Result<Vec<(usize, Lexeme<'_>)>, QueryError> {
+    let mut lexemes = Vec::new();
+    let mut rest = text.char_indices().peekable();
+    while let Some(&(start, c)) = rest.peek() {
+        if c.is_whitespace() {
+            rest.next();
+            continue;
+        }
+        let lexeme = match c {
+            '(' => {
+                rest.next();
+                Lexeme::LParen
+            }
+            ')' => {
+                rest.next();
+                Lexeme::RParen
+            }
+            '"' => {
+                let body = &text[start + 1..];
+                let end = body
+                    .find('"')
+                    .ok_or(QueryError::UnclosedQuote { position: start })?;
+                let close = start + 1 + end;
+                while rest.next_if(|&(i, _)| i <= close).is_some() {}
+                Lexeme::Phrase(&body[..end])
+            }
+            _ => {
+                let mut end = text.len();
+                while let Some(&(i, c)) = rest.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        end = i;
+                        break;
+                    }
+                    rest.next();
+                }
+                match &text[start..end] {
+                    "AND" => Lexeme::And,
+                    "OR" => Lexeme::Or,
+                    "NOT" => Lexeme::Not,
+                    word => Lexeme::Word(word),
+                }
+            }
+        };
+        lexemes.push((start, lexeme));
+    }
+    Ok(lexemes)
+}
+
+/// Recursive descent parser over a list of lexemes.
+struct Parser<'q> {
+    lexemes: Vec<(usize, Lexeme<'q>)>,
+    next: usize,
+    /// Parentheses and `NOT`s around the current lexeme.
+    depth: usize,
+    /// Words and phrases parsed so far.
+    terms: usize,
+}
+
+impl<'q> Parser<'q> {
+    fn peek(&self) -> Option<&Lexeme<'q>> {
+        self.lexemes.get(self.next).map(|(_, lexeme)| lexeme)
+    }
+
+    fn advance(&mut self) -> Option<(usize, Lexeme<'q>)> {
+        let lexeme = self.lexemes.get(self.next).cloned();
+        self.next += 1;
+        lexeme
+    }
+
+    /// Parse with `parse` one level deeper, for the `(` or
+    /// `NOT` at `position`.
+    fn nested<F>(&mut self, position: usize, parse: F) -> Result<Query, QueryError>
+    where
+        F: FnOnce(&mut Self) -> Result<Query, QueryError>,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(QueryError::TooDeep { position });
+        }
+        self.depth += 1;
+        let query = parse(self);
+        self.depth -= 1;
+        query
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Lexeme::Or) {
+            self.advance();
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Lexeme::And) => {
+                    self.advance();
+                }
+                Some(Lexeme::Not | Lexeme::LParen | Lexeme::Phrase(_) | Lexeme::Word(_)) => (),
+                _ => break,
+            }
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        if self.peek() == Some(&Lexeme::Not) {
+            let (position, _) = self.advance().expect("NOT was peeked");
+            let query = self.nested(position, Self::unary)?;
+            return Ok(Query::Not(Box::new(query)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryError> {
+        let (position, lexeme) = self.advance().ok_or(QueryError::UnexpectedEnd)?;
+        if matches!(lexeme, Lexeme::Phrase(_) | Lexeme::Word(_)) {
+            if self.terms == MAX_TERMS {
+                return Err(QueryError::TooManyTerms { position });
+            }
+            self.terms += 1;
+        }
+        match lexeme {
+            Lexeme::LParen => {
+                let query = self.nested(position, Self::or)?;
+                match self.advance() {
+                    Some((_, Lexeme::RParen)) => Ok(query),
+                    _ => Err(QueryError::UnclosedParen { position }),
+                }
+            }
+            Lexeme::Phrase(text) => phrase(position, text),
+            Lexeme::Word(text) => word(position, text),
+            Lexeme::RParen => Err(unexpected(position, ")")),
+            Lexeme::And => Err(unexpected(position, "AND")),
+            Lexeme::Or => Err(unexpected(position, "OR")),
+            Lexeme::Not => unreachable!("NOT is handled by unary"),
+        }
+    }
+}
+
+fn unexpected(position: usize, found: &str) -> QueryError {
+    QueryError::Unexpected {
+        position,
+        found: found.to_string(),
+    }
+}
+
+/// Parse a search word, with its `*` or `~` suffix if any.
+fn word(position: usize, text: &str) -> Result<Query, QueryError> {
+    let (base, make): (&str, fn(String) -> Query) = if let Some(base) = text.strip_suffix('*') {
+        (base, Query::Prefix)
+    } else if let Some((base, distance)) = text.split_once('~') {
+        let distance = if distance.is_empty() {
+            DEFAULT_DISTANCE
+        } else {
+            distance.parse().map_err(|_| QueryError::InvalidDistance {
+                position: position + base.len() + 1,
+                text: distance.to_string(),
+            })?
+        };
+        let word = single_word(position, base)?;
+        return Ok(Query::Fuzzy(word, distance));
+    } else {
+        (text, Query::Term)
+    };
+    Ok(make(single_word(position, base)?))
+}
+
+/// Parse the body `text` of the phrase whose opening `"` is
+/// at `position`. Each whitespace-separated part must be a
+/// word.
+fn phrase(position: usize, text: &str) -> Result<Query, QueryError> {
+    let mut words = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if c.is_whitespace() {
+            if start < i {
+                single_word(position + 1 + start, &text[start..i])?;
+                words += 1;
+            }
+            start = i + c.len_utf8();
+        }
+    }
+    if words == 0 {
+        return Err(QueryError::EmptyPhrase { position });
+    }
+    Ok(Query::Phrase(text.to_string()))
+}
+
+/// The one word in `text`, trimmed of punctuation.
+fn single_word(position: usize, text: &str) -> Result<String, QueryError> {
+    let tokenizer = WordTokenizer::new();
+    let mut tokens = tokenizer.tokens(text);
+    match (tokens.next(), tokens.next()) {
+        (Some(word), None) => Ok(word.into_owned()),
+        _ => Err(QueryError::NotAWord {
+            position,
+            text: text.to_string(),
+        }),
+    }
+}
+
+impl Query {
+    /// Parse a query.
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser {
+            lexemes: lex(text)?,
+            next: 0,
+            depth: 0,
+            terms: 0,
+        };
+        let query = parser.or()?;
+        match parser.advance() {
+            None => Ok(query),
+            Some((position, Lexeme::RParen)) => Err(unexpected(position, ")")),
+            Some((position, lexeme)) => Err(unexpected(position, &format!("{:?}", lexeme))),
+        }
+    }
+
+    /// Does the text counted by `bag` match this query?
+    ///
+    /// Phrases of more than one word only match if the bag
+    /// counts n-grams long enough to hold them.
+    pub fn matches(&self, bag: &Bbow<'_>) -> bool {
+        match self {
+            Query::Term(word) | Query::Phrase(word) => bag.match_count(word) > 0,
+            Query::Prefix(prefix) => {
+                let prefix = bag.normalizer.normalize(Cow::Borrowed(prefix.as_str()));
+                bag.ngrams(1).any(|w| w.starts_with(prefix.as_ref()))
+            }
+            Query::Fuzzy(word, distance) => match bag.key(word) {
+                Ok(Some(key)) => bag.ngrams(1).any(|w| within(&key, w, *distance)),
+                _ => false,
+            },
+            Query::And(left, right) => left.matches(bag) && right.matches(bag),
+            Query::Or(left, right) => left.matches(bag) || right.matches(bag),
+            Query::Not(query) => !query.matches(bag),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Query::parse(text)
+    }
+}
+
+impl InvertedIndex<'_> {
+    /// The ids of the documents matching `query`.
+    pub fn search(&self, query: &Query) -> Vec<&str> {
+        self.ids_of(&self.evaluate(query))
+    }
+
+    /// Parse `query` and return the ids of the documents
+    /// matching it.
+    pub fn query(&self, query: &str) -> Result<Vec<&str>, QueryError> {
+        Ok(self.search(&Query::parse(query)?))
+    }
+
+    /// Numbers of the documents matching `query`, in order.
+    fn evaluate(&self, query: &Query) -> Vec<usize> {
+        match query {
+            Query::Term(word) | Query::Phrase(word) => {
+                self.phrase_documents(&self.terms(word).unwrap_or_default())
+            }
+            Query::Prefix(prefix) => {
+                let prefix = self
+                    .template()
+                    .normalizer
+                    .normalize(Cow::Borrowed(prefix.as_str()));
+                self.terms_with_prefix(&prefix)
+                    .map(|term| self.term_documents(term))
+                    .fold(Vec::new(), |acc, set| union(&acc, &set))
+            }
+            Query::Fuzzy(word, distance) => match self.terms(word).unwrap_or_default().first() {
+                Some(key) => self
+                    .vocabulary()
+                    .filter(|term| within(key, term, *distance))
+                    .map(|term| self.term_documents(term))
+                    .fold(Vec::new(), |acc, set| union(&acc, &set)),
+                None => Vec::new(),
+            },
+            Query::And(left, right) => intersect(&self.evaluate(left), &self.evaluate(right)),
+            Query::Or(left, right) => union(&self.evaluate(left), &self.evaluate(right)),
+            Query::Not(query) => difference(&self.all_documents(), &self.evaluate(query)),
+        }
+    }
+}
+
+/// Is the Levenshtein distance between `a` and `b`, in
+/// characters, at most `limit`?
+fn within(a: &str, b: &str, limit: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > limit {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|&d| d > limit) {
+            return false;
+        }
+        previous = current;
+    }
+    previous[b.len()] <= limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::index;
+
+    fn term(word: &str) -> Box<Query> {
+        Box::new(Query::Term(word.to_string()))
+    }
+
+    const DOCUMENTS: &[(&str, &str)] = &[
+        ("a", "This is a test string for test purposes"),
+        ("b", "Can't stop this! Stop!"),
+        ("c", "It ain't over untïl it ain't, over."),
+        ("d", "Stop testing words"),
+        ("e", "Is it over? Until then."),
+    ];
+
+    fn search<'i>(index: &'i InvertedIndex, query: &str) -> Vec<&'i str> {
+        index.query(query).unwrap()
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        assert_eq!(
+            Query::Or(term("a"), Box::new(Query::And(term("b"), term("c")))),
+            Query::parse("a OR b AND c").unwrap()
+        );
+        assert_eq!(
+            Query::And(term("a"), Box::new(Query::Not(term("b")))),
+            Query::parse("a AND NOT b").unwrap()
+        );
+        assert_eq!(
+            Query::And(Box::new(Query::Not(term("a"))), term("b")),
+            Query::parse("NOT a b").unwrap()
+        );
+        assert_eq!(
+            Query::And(Box::new(Query::Or(term("a"), term("b"))), term("c")),
+            Query::parse("(a OR b) c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_words() {
+        assert_eq!(Query::Prefix("test".into()), "test*".parse().unwrap());
+        assert_eq!(Query::Fuzzy("word".into(), 1), "word~1".parse().unwrap());
+        assert_eq!(Query::Fuzzy("word".into(), 2), "word~".parse().unwrap());
+        assert_eq!(Query::Term("Stop".into()), "Stop!".parse().unwrap());
+        assert_eq!(Query::Term("and".into()), "and".parse().unwrap());
+    }
+
+    #[test]
+    fn test_parse_quoting() {
+        assert_eq!(
+            Query::Phrase("over untïl".into()),
+            Query::parse("\"over untïl\"").unwrap()
+        );
+        assert_eq!(
+            Query::Or(Box::new(Query::Phrase("AND (OR)".into())), term("x")),
+            Query::parse("\"AND (OR)\" OR x").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use QueryError::*;
+
+        assert_eq!(Err(UnexpectedEnd), Query::parse(""));
+        assert_eq!(Err(UnexpectedEnd), Query::parse("stop AND"));
+        assert_eq!(Err(UnexpectedEnd), Query::parse("NOT"));
+        assert_eq!(
+            Err(Unexpected {
+                position: 0,
+                found: "OR".into()
+            }),
+            Query::parse("OR stop")
+        );
+        assert_eq!(
+            Err(Unexpected {
+                position: 4,
+                found: ")".into()
+            }),
+            Query::parse("stop)")
+        );
+        assert_eq!(
+            Err(UnclosedQuote { position: 5 }),
+            Query::parse("stop \"this")
+        );
+        assert_eq!(
+            Err(UnclosedParen { position: 0 }),
+            Query::parse("(stop this")
+        );
+        assert_eq!(
+            Err(NotAWord {
+                position: 0,
+                text: "b-banana".into()
+            }),
+            Query::parse("b-banana")
+        );
+        assert_eq!(Err(EmptyPhrase { position: 0 }), Query::parse("\" \""));
+        assert_eq!(
+            Err(NotAWord {
+                position: 1,
+                text: "...".into()
+            }),
+            Query::parse("\"...\"")
+        );
+        assert_eq!(
+            Err(NotAWord {
+                position: 6,
+                text: "ain't".into()
+            }),
+            Query::parse("over \"ain't over\"")
+        );
+        assert_eq!(
+            Err(NotAWord {
+                position: 8,
+                text: "b-banana".into()
+            }),
+            Query::parse("\"split  b-banana\"")
+        );
+        assert_eq!(
+            Err(InvalidDistance {
+                position: 5,
+                text: "x".into()
+            }),
+            Query::parse("word~x")
+        );
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        use QueryError::*;
+
+        let parens = "(".repeat(10_000);
+        assert_eq!(Err(TooDeep { position: 64 }), Query::parse(&parens));
+        let nots = "NOT ".repeat(10_000);
+        assert_eq!(Err(TooDeep { position: 256 }), Query::parse(&nots));
+        let nested = format!("{}a{}", "(NOT ".repeat(32), ")".repeat(32));
+        assert!(Query::parse(&nested).is_ok());
+
+        let words = "a ".repeat(10_000);
+        assert_eq!(Err(TooManyTerms { position: 2048 }), Query::parse(&words));
+        let index = index(DOCUMENTS);
+        let longest = vec!["stop"; 1024].join(" OR ");
+        assert_eq!(vec!["b", "d"], search(&index, &longest));
+    }
+
+    #[test]
+    fn test_search() {
+        let index = index(DOCUMENTS);
+        assert_eq!(vec!["b", "d"], search(&index, "stop"));
+        assert_eq!(vec!["d"], search(&index, "stop AND NOT this"));
+        assert_eq!(vec!["d"], search(&index, "stop NOT this"));
+        assert_eq!(vec!["a", "c", "e"], search(&index, "NOT stop"));
+        assert_eq!(vec!["a", "b", "d"], search(&index, "test OR stop"));
+        assert_eq!(vec!["c", "e"], search(&index, "over"));
+        assert_eq!(vec!["c"], search(&index, "\"over untïl\""));
+        assert!(search(&index, "\"over it\"").is_empty());
+        assert_eq!(vec!["a"], search(&index, "\"test string\""));
+        assert!(search(&index, "\"string test\"").is_empty());
+    }
+
+    #[test]
+    fn test_search_prefix_and_fuzzy() {
+        let index = index(DOCUMENTS);
+        assert_eq!(vec!["a", "d"], search(&index, "test*"));
+        assert_eq!(vec!["a", "d"], search(&index, "Test*"));
+        assert_eq!(vec!["d"], search(&index, "word~1"));
+        assert_eq!(vec!["d"], search(&index, "wordz~1"));
+        assert!(search(&index, "wart~1").is_empty());
+        assert_eq!(vec!["a"], search(&index, "tests~1"));
+        assert_eq!(vec!["d"], search(&index, "testin~1"));
+    }
+
+    #[cfg(feature = "stemming")]
+    #[test]
+    fn test_prefix_matches_stems() {
+        use crate::{Language, SnowballStemmer};
+
+        let template = Bbow::new().with_stemmer(SnowballStemmer::new(Language::English));
+        let mut index = InvertedIndex::new().with_template(template);
+        index.add_text("a", "running tests");
+        assert_eq!(vec!["a"], search(&index, "tests"));
+        assert_eq!(vec!["a"], search(&index, "test*"));
+        assert!(search(&index, "tests*").is_empty());
+    }
+
+    #[test]
+    fn test_matches_bag() {
+        let bag = Bbow::new()
+            .with_ngrams(1..=2)
+            .extend_from_text("It ain't over untïl it ain't, over.");
+        let matches = |query: &str| Query::parse(query).unwrap().matches(&bag);
+        assert!(matches("over AND NOT stop"));
+        assert!(matches("\"it over\""));
+        assert!(!matches("\"over it\""));
+        assert!(matches("\"over untïl\""));
+        assert!(!matches("\"untïl over\""));
+        assert!(matches("unt*"));
+        assert!(matches("untol~1"));
+        assert!(!matches("stop OR test"));
+    }
+
+    #[test]
+    fn test_within() {
+        assert!(within("word", "word", 0));
+        assert!(within("word", "wort", 1));
+        assert!(within("word", "words", 1));
+        assert!(within("word", "ord", 1));
+        assert!(!within("word", "wart", 1));
+        assert!(within("untïl", "until", 1));
+    }
+}
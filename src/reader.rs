@@ -0,0 +1,236 @@
+//! Counting words from a stream of bytes.
+//!
+//! [OwnedBbow::extend_from_reader] reads its input a buffer
+//! at a time, so that large files need not be held in
+//! memory. Text is handed to the tokenizer in pieces that
+//! end at whitespace, so words are never split between two
+//! buffers, and n-grams are carried over from one piece to
+//! the next: the result is the same as reading the whole
+//! input into a string and counting it at once.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::str;
+
+use crate::owned::owned;
+use crate::{OwnedBbow, Tokenizer, WordTokenizer};
+
+/// What to do with bytes that are not valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Fail with an [io::ErrorKind::InvalidData] error.
+    #[default]
+    Strict,
+    /// Replace each invalid sequence with U+FFFD REPLACEMENT
+    /// CHARACTER, as [String::from_utf8_lossy] does.
+    Lossy,
+}
+
+/// Decode as much of `bytes` as possible onto the end of
+/// `text`, leaving in `bytes` only an incomplete character
+/// that may be finished by the next read. At the end of the
+/// input, an incomplete character is invalid.
+fn decode(
+    bytes: &mut Vec<u8>,
+    text: &mut String,
+    policy: Utf8Policy,
+    at_end: bool,
+) -> io::Result<()> {
+    loop {
+        let error = match str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                bytes.clear();
+                return Ok(());
+            }
+            Err(error) => error,
+        };
+        let valid = error.valid_up_to();
+        text.push_str(str::from_utf8(&bytes[..valid]).expect("prefix is valid UTF-8"));
+        let invalid = match error.error_len() {
+            Some(len) => len,
+            None if at_end => bytes.len() - valid,
+            None => {
+                bytes.drain(..valid);
+                return Ok(());
+            }
+        };
+        if policy == Utf8Policy::Strict {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+        bytes.drain(..valid + invalid);
+    }
+}
+
+/// Read `reader` to the end, passing its text to `f` in
+/// pieces that each end with whitespace (or at the end of
+/// the input).
+fn for_each_piece<R, F>(mut reader: R, policy: Utf8Policy, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(&str),
+{
+    let mut bytes = Vec::new();
+    let mut text = String::new();
+    loop {
+        let buffer = match reader.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        let (read, at_end) = (buffer.len(), buffer.is_empty());
+        bytes.extend_from_slice(buffer);
+        reader.consume(read);
+
+        // The text held back from earlier reads has no
+        // whitespace, so only the new text is searched.
+        // On an error, the text decoded before it is still
+        // passed on, up to its last whitespace.
+        let searched = text.len();
+        let decoded = decode(&mut bytes, &mut text, policy, at_end);
+        let end = if at_end && decoded.is_ok() {
+            text.len()
+        } else {
+            text[searched..]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map_or(0, |(i, c)| searched + i + c.len_utf8())
+        };
+        if end > 0 {
+            f(&text[..end]);
+            text.drain(..end);
+        }
+        decoded?;
+        if at_end {
+            return Ok(());
+        }
+    }
+}
+
+impl OwnedBbow {
+    /// Read text from `reader` until it is exhausted, and
+    /// record its words in this BBOW as
+    /// [OwnedBbow::extend_from_text] would. Bytes that are
+    /// not valid UTF-8 are handled according to `policy`.
+    ///
+    /// The input is read a buffer at a time, without
+    /// holding all of it in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `reader` other than
+    /// [io::ErrorKind::Interrupted], or an
+    /// [io::ErrorKind::InvalidData] error for invalid UTF-8
+    /// under [Utf8Policy::Strict]. The words read before the
+    /// error stay counted in this BBOW, except for a word that
+    /// the error may have cut short.
+    pub fn extend_from_reader<R: BufRead>(
+        &mut self,
+        reader: R,
+        policy: Utf8Policy,
+    ) -> io::Result<()> {
+        self.extend_from_reader_with(&WordTokenizer::new(), reader, policy)
+    }
+
+    /// Like [OwnedBbow::extend_from_reader], but split the
+    /// text into words with `tokenizer`. The tokenizer
+    /// should not join words across whitespace.
+    pub fn extend_from_reader_with<T, R>(
+        &mut self,
+        tokenizer: &T,
+        reader: R,
+        policy: Utf8Policy,
+    ) -> io::Result<()>
+    where
+        T: Tokenizer + ?Sized,
+        R: BufRead,
+    {
+        let mut window = VecDeque::with_capacity(*self.0.ngrams.end());
+        for_each_piece(reader, policy, |piece| {
+            self.0
+                .count_words_with(tokenizer.tokens(piece), &mut window, owned);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::pairs;
+    use crate::Bbow;
+    use std::io::BufReader;
+
+    const TEXT: &str = "It ain't over untïl it ain't, over. Can't stop this! Stop!";
+
+    /// Read `bytes` through a buffer of `capacity` bytes.
+    fn read(bytes: &[u8], capacity: usize, policy: Utf8Policy) -> io::Result<OwnedBbow> {
+        let mut bag = OwnedBbow::from(Bbow::new().with_ngrams(1..=2));
+        bag.extend_from_reader(BufReader::with_capacity(capacity, bytes), policy)?;
+        Ok(bag)
+    }
+
+    #[test]
+    fn test_split_buffers() {
+        let expected = Bbow::new().with_ngrams(1..=2).extend_from_text(TEXT);
+        for capacity in 1..=TEXT.len() + 1 {
+            let bag = read(TEXT.as_bytes(), capacity, Utf8Policy::Strict).unwrap();
+            assert_eq!(pairs(&expected), pairs(&bag), "capacity {}", capacity);
+        }
+    }
+
+    #[test]
+    fn test_strict() {
+        let error = read(b"stop \xff this", 4, Utf8Policy::Strict).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        // An incomplete character at the very end.
+        let error = read(b"stop this \xc3", 4, Utf8Policy::Strict).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn test_lossy() {
+        let bytes = b"stop \xff this unt\xc3\xafl t\xc3 \xe2\x82";
+        let text = String::from_utf8_lossy(bytes);
+        let expected = Bbow::new().with_ngrams(1..=2).extend_from_text(&text);
+        for capacity in 1..=bytes.len() {
+            let bag = read(bytes, capacity, Utf8Policy::Lossy).unwrap();
+            assert_eq!(pairs(&expected), pairs(&bag));
+        }
+        let bag = read(bytes, 3, Utf8Policy::Lossy).unwrap();
+        assert_eq!(1, bag.match_count("untïl"));
+        assert_eq!(1, bag.match_count("stop this"));
+    }
+
+    #[test]
+    fn test_read_error() {
+        struct Failing;
+
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+
+        let mut bag = OwnedBbow::new().extend_from_text("stop");
+        let error = bag
+            .extend_from_reader(BufReader::new(Failing), Utf8Policy::Lossy)
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::Other, error.kind());
+        assert_eq!(1, bag.match_count("stop"));
+    }
+
+    #[test]
+    fn test_error_keeps_counts() {
+        let mut bag = OwnedBbow::new().extend_from_text("stop");
+        let bytes: &[u8] = b"stop this \xff test";
+        let error = bag
+            .extend_from_reader(BufReader::with_capacity(4, bytes), Utf8Policy::Strict)
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(2, bag.match_count("stop"));
+        assert_eq!(1, bag.match_count("this"));
+        assert_eq!(0, bag.match_count("test"));
+    }
+}
@@ -0,0 +1,85 @@
+//! Serialization of bags with `serde` (feature `serde`).
+//!
+//! A bag is serialized as a map from each of its words to
+//! its count, in sorted word order, so the same bag always
+//! serializes the same way. In JSON, the bag of
+//! `"Can't stop this! Stop!"` is
+//!
+//! ```text
+//! {"stop":2,"this":1}
+//! ```
+//!
+//! Only the counts are serialized: a deserialized bag has
+//! default settings (normalizer, stop words and so on), and
+//! owns its words. Words with a count of 0 are dropped.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Bbow, OwnedBbow};
+
+impl Serialize for Bbow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Bbow<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let counts = BTreeMap::<String, usize>::deserialize(deserializer)?;
+        Ok(counts
+            .into_iter()
+            .map(|(word, count)| (Cow::Owned(word), count))
+            .collect())
+    }
+}
+
+impl Serialize for OwnedBbow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedBbow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Bbow::deserialize(deserializer).map(OwnedBbow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let bag = Bbow::new().extend_from_text("Can't stop this! Stop!");
+        assert_eq!(
+            r#"{"stop":2,"this":1}"#,
+            serde_json::to_string(&bag).unwrap()
+        );
+        assert_eq!("{}", serde_json::to_string(&Bbow::new()).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "It ain't over untïl it ain't, over.";
+        let bag = Bbow::new().with_ngrams(1..=2).extend_from_text(text);
+        let json = serde_json::to_string(&bag).unwrap();
+        let back: Bbow<'static> = serde_json::from_str(&json).unwrap();
+        assert!(back.iter().eq(bag.iter()));
+        assert_eq!(1, back.match_count("untïl"));
+
+        let owned: OwnedBbow = serde_json::from_str(&json).unwrap();
+        assert_eq!(json, serde_json::to_string(&owned).unwrap());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let bag: Bbow = serde_json::from_str(r#"{"b":1,"a":2,"c":0}"#).unwrap();
+        assert!(bag.iter().eq(vec![("a", 2), ("b", 1)]));
+        assert!(serde_json::from_str::<Bbow>(r#"{"a":-1}"#).is_err());
+        assert!(serde_json::from_str::<Bbow>(r#"["a"]"#).is_err());
+    }
+}
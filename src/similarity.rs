@@ -0,0 +1,214 @@
+//! Similarity and distance measures between bags.
+//!
+//! Each measure walks the sorted maps of both bags together,
+//! so it takes time linear in the combined number of unique
+//! words.
+//!
+//! When both bags are empty they are considered identical:
+//! similarities are 1 and distances are 0.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::btree_map;
+use std::iter::Peekable;
+
+use crate::Bbow;
+
+/// The counts of each word in either of two bags, in word
+/// order. A word missing from one bag has count 0 there.
+pub(crate) struct Merged<'s, 'a, 'b> {
+    left: Peekable<btree_map::Iter<'s, Cow<'a, str>, usize>>,
+    right: Peekable<btree_map::Iter<'s, Cow<'b, str>, usize>>,
+}
+
+impl<'s, 'a, 'b> Merged<'s, 'a, 'b> {
+    pub(crate) fn new(left: &'s Bbow<'a>, right: &'s Bbow<'b>) -> Self {
+        Merged {
+            left: left.words.iter().peekable(),
+            right: right.words.iter().peekable(),
+        }
+    }
+}
+
+impl Iterator for Merged<'_, '_, '_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((l, _)), Some((r, _))) => l.as_ref().cmp(r.as_ref()),
+        };
+        Some(match order {
+            Ordering::Less => (*self.left.next()?.1, 0),
+            Ordering::Greater => (0, *self.right.next()?.1),
+            Ordering::Equal => (*self.left.next()?.1, *self.right.next()?.1),
+        })
+    }
+}
+
+/// `numerator / denominator`, or `if_empty` when the
+/// denominator is 0.
+fn ratio(numerator: f64, denominator: f64, if_empty: f64) -> f64 {
+    if denominator == 0.0 {
+        if_empty
+    } else {
+        numerator / denominator
+    }
+}
+
+impl Bbow<'_> {
+    /// Cosine of the angle between the count vectors of the
+    /// two bags, from 0 (no words in common) to 1 (same
+    /// proportions).
+    pub fn cosine_similarity(&self, other: &Bbow<'_>) -> f64 {
+        let (mut dot, mut left, mut right) = (0.0, 0.0, 0.0);
+        for (l, r) in Merged::new(self, other) {
+            let (l, r) = (l as f64, r as f64);
+            dot += l * r;
+            left += l * l;
+            right += r * r;
+        }
+        if left == 0.0 && right == 0.0 {
+            return 1.0;
+        }
+        ratio(dot, left.sqrt() * right.sqrt(), 0.0)
+    }
+
+    /// Jaccard index of the two bags' sets of words: the
+    /// number of shared words over the number of words in
+    /// either. Counts are ignored.
+    pub fn jaccard(&self, other: &Bbow<'_>) -> f64 {
+        let (mut shared, mut either) = (0, 0);
+        for (l, r) in Merged::new(self, other) {
+            either += 1;
+            if l > 0 && r > 0 {
+                shared += 1;
+            }
+        }
+        ratio(shared as f64, either as f64, 1.0)
+    }
+
+    /// Weighted (Ruzicka) Jaccard index: the sum over words
+    /// of the smaller count over the sum of the larger count.
+    pub fn weighted_jaccard(&self, other: &Bbow<'_>) -> f64 {
+        let (mut min, mut max) = (0, 0);
+        for (l, r) in Merged::new(self, other) {
+            min += l.min(r);
+            max += l.max(r);
+        }
+        ratio(min as f64, max as f64, 1.0)
+    }
+
+    /// Sørensen–Dice coefficient of the two bags' sets of
+    /// words: twice the number of shared words over the
+    /// total number of unique words in each.
+    pub fn dice(&self, other: &Bbow<'_>) -> f64 {
+        let mut shared = 0;
+        for (l, r) in Merged::new(self, other) {
+            if l > 0 && r > 0 {
+                shared += 1;
+            }
+        }
+        ratio(2.0 * shared as f64, (self.len() + other.len()) as f64, 1.0)
+    }
+
+    /// Bray–Curtis dissimilarity of the two bags' counts,
+    /// from 0 (identical) to 1 (no words in common).
+    pub fn bray_curtis(&self, other: &Bbow<'_>) -> f64 {
+        let (mut min, mut total) = (0, 0);
+        for (l, r) in Merged::new(self, other) {
+            min += l.min(r);
+            total += l + r;
+        }
+        1.0 - ratio(2.0 * min as f64, total as f64, 1.0)
+    }
+
+    /// Jensen–Shannon divergence between the two bags' word
+    /// frequency distributions, in bits: from 0 (same
+    /// distribution) to 1 (no words in common).
+    ///
+    /// An empty bag is taken to be as far as possible from
+    /// a nonempty one.
+    pub fn jensen_shannon(&self, other: &Bbow<'_>) -> f64 {
+        let (left_total, right_total) = (self.count() as f64, other.count() as f64);
+        match (left_total == 0.0, right_total == 0.0) {
+            (true, true) => return 0.0,
+            (true, false) | (false, true) => return 1.0,
+            (false, false) => (),
+        }
+        // Each term p log(p / m) with m = (p + q) / 2; a
+        // zero p contributes nothing.
+        let term = |p: f64, m: f64| if p > 0.0 { p * (p / m).log2() } else { 0.0 };
+        let mut divergence = 0.0;
+        for (l, r) in Merged::new(self, other) {
+            let p = l as f64 / left_total;
+            let q = r as f64 / right_total;
+            let m = (p + q) / 2.0;
+            divergence += term(p, m) + term(q, m);
+        }
+        // Rounding can leave a tiny value outside [0, 1].
+        (divergence / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{bag, close};
+
+    #[test]
+    fn test_merged() {
+        let (a, b) = (bag("a a c"), bag("b c c c d"));
+        let merged: Vec<_> = Merged::new(&a, &b).collect();
+        assert_eq!(vec![(2, 0), (0, 1), (1, 3), (0, 1)], merged);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!(close(1.0, bag("a b").cosine_similarity(&bag("b b a a"))));
+        assert!(close(0.0, bag("a b").cosine_similarity(&bag("c"))));
+        // (1, 1, 0) . (0, 1, 1) = 1, |x| = |y| = sqrt 2
+        assert!(close(0.5, bag("a b").cosine_similarity(&bag("b c"))));
+        assert!(close(0.0, bag("a").cosine_similarity(&bag(""))));
+    }
+
+    #[test]
+    fn test_jaccard() {
+        assert!(close(1.0 / 3.0, bag("a b").jaccard(&bag("b c c"))));
+        assert!(close(1.0, bag("").jaccard(&bag(""))));
+    }
+
+    #[test]
+    fn test_weighted_jaccard() {
+        // min: a 0, b 1, c 0 = 1; max: a 1, b 1, c 2 = 4
+        assert!(close(0.25, bag("a b").weighted_jaccard(&bag("b c c"))));
+    }
+
+    #[test]
+    fn test_dice() {
+        assert!(close(0.5, bag("a b").dice(&bag("b c c"))));
+        assert!(close(0.0, bag("a").dice(&bag(""))));
+    }
+
+    #[test]
+    fn test_bray_curtis() {
+        // 1 - 2 * 1 / (2 + 3)
+        assert!(close(0.6, bag("a b").bray_curtis(&bag("b c c"))));
+        assert!(close(0.0, bag("a b").bray_curtis(&bag("B, a."))));
+    }
+
+    #[test]
+    fn test_jensen_shannon() {
+        assert!(close(0.0, bag("a b").jensen_shannon(&bag("a a b b"))));
+        assert!(close(1.0, bag("a b").jensen_shannon(&bag("c d"))));
+        assert!(close(1.0, bag("a b").jensen_shannon(&bag(""))));
+        // p = (1, 0), q = (1/2, 1/2): m = (3/4, 1/4)
+        let expected = (1.0 * (1.0f64 / 0.75).log2()
+            + 0.5 * (0.5f64 / 0.75).log2()
+            + 0.5 * (0.5f64 / 0.25).log2())
+            / 2.0;
+        assert!(close(expected, bag("a").jensen_shannon(&bag("a b"))));
+    }
+}
@@ -0,0 +1,316 @@
+//! Sparse feature vectors for machine learning.
+//!
+//! A bag is turned into a vector by numbering its words
+//! with a fixed [Vocabulary]: the vector has one entry for
+//! each word of the vocabulary, holding the count of that
+//! word in the bag. Most entries are 0, so only the others
+//! are stored, as index/value pairs in index order. Words of
+//! the bag that are not in the vocabulary are left out.
+//!
+//! The vectors of a whole [Corpus] can be gathered into a
+//! [CsrMatrix], with one row per document, in the
+//! compressed sparse row layout used by most numeric
+//! libraries.
+
+use crate::{Bbow, Corpus, Vocabulary};
+
+/// A vector norm, used to scale vectors to unit length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Norm {
+    /// The sum of absolute values: normalized counts are
+    /// word frequencies.
+    L1,
+    /// The Euclidean length: normalized vectors have a dot
+    /// product equal to their cosine similarity.
+    L2,
+}
+
+impl Norm {
+    /// The norm of a vector with the given entries.
+    fn of(self, values: &[f64]) -> f64 {
+        match self {
+            Norm::L1 => values.iter().map(|v| v.abs()).sum(),
+            Norm::L2 => values.iter().map(|v| v * v).sum::<f64>().sqrt(),
+        }
+    }
+}
+
+/// Divide `values` by their `norm`, unless it is 0.
+fn scale(values: &mut [f64], norm: Norm) {
+    let length = norm.of(values);
+    if length > 0.0 {
+        values.iter_mut().for_each(|v| *v /= length);
+    }
+}
+
+/// A vector of `dimension` entries, of which only the
+/// nonzero ones are stored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    dimension: usize,
+    indices: Vec<u32>,
+    values: Vec<f64>,
+}
+
+impl SparseVector {
+    /// Make a vector from index/value pairs, which must be in
+    /// increasing index order and less than `dimension`.
+    /// Zero values are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the indices are out of order or out of
+    /// range.
+    pub fn from_pairs<I>(dimension: usize, pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, f64)>,
+    {
+        let (indices, values): (Vec<_>, Vec<_>) =
+            pairs.into_iter().filter(|&(_, value)| value != 0.0).unzip();
+        assert!(
+            indices.windows(2).all(|w| w[0] < w[1]),
+            "indices out of order"
+        );
+        assert!(
+            indices.last().is_none_or(|&i| (i as usize) < dimension),
+            "index out of range"
+        );
+        SparseVector {
+            dimension,
+            indices,
+            values,
+        }
+    }
+
+    /// Number of entries in the vector, zero or not.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Indices of the nonzero entries, in increasing order.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Values of the nonzero entries, in index order.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// The nonzero entries as index/value pairs, in index
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f64)> + '_ {
+        self.indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    /// Number of nonzero entries.
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The entry at `index`: 0 if it is not stored.
+    pub fn get(&self, index: u32) -> f64 {
+        self.indices
+            .binary_search(&index)
+            .map_or(0.0, |i| self.values[i])
+    }
+
+    /// Scale this vector to have length 1 under `norm`. The
+    /// zero vector is left as it is.
+    pub fn normalize(mut self, norm: Norm) -> Self {
+        scale(&mut self.values, norm);
+        self
+    }
+
+    /// The dense form of this vector.
+    pub fn to_dense(&self) -> Vec<f64> {
+        let mut dense = vec![0.0; self.dimension];
+        for (index, value) in self.iter() {
+            dense[index as usize] = value;
+        }
+        dense
+    }
+}
+
+/// A sparse matrix in compressed sparse row (CSR) form.
+///
+/// The nonzero entries of row `r` are at positions
+/// `indptr[r]..indptr[r + 1]` of `indices` (their columns)
+/// and `data` (their values).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsrMatrix {
+    columns: usize,
+    indptr: Vec<usize>,
+    indices: Vec<u32>,
+    data: Vec<f64>,
+}
+
+impl CsrMatrix {
+    /// Stack `rows`, which must all have dimension `columns`,
+    /// into a matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a row has a different dimension.
+    pub fn from_rows<I>(columns: usize, rows: I) -> Self
+    where
+        I: IntoIterator<Item = SparseVector>,
+    {
+        let mut matrix = CsrMatrix {
+            columns,
+            indptr: vec![0],
+            ..CsrMatrix::default()
+        };
+        for row in rows {
+            assert_eq!(columns, row.dimension, "row has the wrong dimension");
+            matrix.indices.extend(row.indices);
+            matrix.data.extend(row.values);
+            matrix.indptr.push(matrix.indices.len());
+        }
+        matrix
+    }
+
+    /// Number of rows.
+    pub fn rows(&self) -> usize {
+        self.indptr.len().saturating_sub(1)
+    }
+
+    /// Number of columns.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of nonzero entries.
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Start of each row in `indices` and `data`, followed by
+    /// the number of nonzero entries.
+    pub fn indptr(&self) -> &[usize] {
+        &self.indptr
+    }
+
+    /// Column of each nonzero entry, row by row.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Value of each nonzero entry, row by row.
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Row `r` of the matrix, or `None` if there is no such
+    /// row.
+    pub fn row(&self, r: usize) -> Option<SparseVector> {
+        let range = *self.indptr.get(r)?..*self.indptr.get(r + 1)?;
+        Some(SparseVector {
+            dimension: self.columns,
+            indices: self.indices[range.clone()].to_vec(),
+            values: self.data[range].to_vec(),
+        })
+    }
+
+    /// Scale each row to have length 1 under `norm`. Rows of
+    /// zeros are left as they are.
+    pub fn normalize(mut self, norm: Norm) -> Self {
+        for bounds in self.indptr.windows(2) {
+            scale(&mut self.data[bounds[0]..bounds[1]], norm);
+        }
+        self
+    }
+}
+
+impl Bbow<'_> {
+    /// The counts of the words of `vocabulary` in this BBOW,
+    /// as a vector indexed by word id. Words not in the
+    /// vocabulary are left out.
+    pub fn to_sparse(&self, vocabulary: &Vocabulary) -> SparseVector {
+        let mut pairs: Vec<_> = self
+            .iter()
+            .filter_map(|(word, count)| Some((vocabulary.id(word)?, count as f64)))
+            .collect();
+        pairs.sort_unstable_by_key(|&(id, _)| id);
+        SparseVector::from_pairs(vocabulary.len(), pairs)
+    }
+}
+
+impl Corpus<'_> {
+    /// The word counts of the documents of this corpus as a
+    /// matrix, with one row per document in id order (as
+    /// given by [Corpus::documents]) and one column per word
+    /// of `vocabulary`.
+    pub fn to_csr(&self, vocabulary: &Vocabulary) -> CsrMatrix {
+        CsrMatrix::from_rows(
+            vocabulary.len(),
+            self.documents().map(|(_, bag)| bag.to_sparse(vocabulary)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::close;
+
+    fn vocabulary() -> Vocabulary {
+        ["this", "stop", "test"].into_iter().collect()
+    }
+
+    #[test]
+    fn test_to_sparse() {
+        let bag = Bbow::new().extend_from_text("Can't stop this! Stop! Banana.");
+        let vector = bag.to_sparse(&vocabulary());
+        assert_eq!(3, vector.dimension());
+        assert_eq!(&[0, 1], vector.indices());
+        assert_eq!(&[1.0, 2.0], vector.values());
+        assert_eq!(0.0, vector.get(2));
+        assert_eq!(vec![1.0, 2.0, 0.0], vector.to_dense());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let vector = SparseVector::from_pairs(4, [(0, 3.0), (2, -4.0)]);
+        let l1 = vector.clone().normalize(Norm::L1);
+        assert!(close(3.0 / 7.0, l1.get(0)));
+        assert!(close(-4.0 / 7.0, l1.get(2)));
+        let l2 = vector.normalize(Norm::L2);
+        assert!(close(0.6, l2.get(0)));
+        assert!(close(-0.8, l2.get(2)));
+        let zero = SparseVector::from_pairs(4, []).normalize(Norm::L2);
+        assert_eq!(0, zero.nnz());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_pairs_out_of_order() {
+        let _ = SparseVector::from_pairs(4, [(2, 1.0), (1, 1.0)]);
+    }
+
+    #[test]
+    fn test_csr() {
+        let mut corpus = Corpus::new();
+        corpus.add_text("b", "This is a test string for test purposes");
+        corpus.add_text("a", "Can't stop this! Stop!");
+        corpus.add_text("c", "Nothing here.");
+        let matrix = corpus.to_csr(&vocabulary());
+        assert_eq!(3, matrix.rows());
+        assert_eq!(3, matrix.columns());
+        assert_eq!(&[0, 2, 4, 4], matrix.indptr());
+        assert_eq!(&[0, 1, 0, 2], matrix.indices());
+        assert_eq!(&[1.0, 2.0, 1.0, 2.0], matrix.data());
+        assert_eq!(
+            corpus.document("a").unwrap().to_sparse(&vocabulary()),
+            matrix.row(0).unwrap()
+        );
+        assert!(matrix.row(3).is_none());
+
+        let matrix = matrix.normalize(Norm::L1);
+        assert!(close(1.0 / 3.0, matrix.row(1).unwrap().get(0)));
+        assert_eq!(0, matrix.row(2).unwrap().nnz());
+    }
+}
@@ -0,0 +1,113 @@
+//! Stemming: reducing inflected words to a common stem, so
+//! that "test", "tests" and "testing" are counted together.
+//!
+//! A [Stemmer] attached to a [Bbow](crate::Bbow) with
+//! [Bbow::with_stemmer](crate::Bbow::with_stemmer) is applied
+//! to each word after normalization and stop word removal.
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// A rule for reducing a word to its stem or lemma.
+///
+/// Stemmers are shared between bags and threads, so they
+/// must be `Send` and `Sync`.
+pub trait Stemmer: fmt::Debug + Send + Sync {
+    /// Reduce the (already normalized) `word` to its stem.
+    /// The word should be returned unchanged, without
+    /// copying, when it is its own stem.
+    fn stem<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str>;
+}
+
+/// A stemmer using the [Snowball](https://snowballstem.org/)
+/// algorithm for a language. For English this is the
+/// "Porter2" refinement of the Porter stemmer.
+///
+/// Requires the `stemming` feature.
+#[cfg(feature = "stemming")]
+pub struct SnowballStemmer {
+    language: crate::Language,
+    stemmer: rust_stemmers::Stemmer,
+}
+
+#[cfg(feature = "stemming")]
+impl SnowballStemmer {
+    /// Make a stemmer for `language`.
+    pub fn new(language: crate::Language) -> Self {
+        use crate::Language::*;
+        use rust_stemmers::Algorithm;
+
+        let algorithm = match language {
+            Dutch => Algorithm::Dutch,
+            English => Algorithm::English,
+            French => Algorithm::French,
+            German => Algorithm::German,
+            Italian => Algorithm::Italian,
+            Portuguese => Algorithm::Portuguese,
+            Spanish => Algorithm::Spanish,
+        };
+        SnowballStemmer {
+            language,
+            stemmer: rust_stemmers::Stemmer::create(algorithm),
+        }
+    }
+}
+
+#[cfg(feature = "stemming")]
+impl fmt::Debug for SnowballStemmer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnowballStemmer")
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+#[cfg(feature = "stemming")]
+impl Stemmer for SnowballStemmer {
+    fn stem<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+        let stem = self.stemmer.stem(&word);
+        // Most stems are a prefix of the word: keep those
+        // zero-copy when the word is.
+        if !word.starts_with(stem.as_ref()) {
+            return Cow::Owned(stem.into_owned());
+        }
+        let len = stem.len();
+        match word {
+            Cow::Borrowed(word) => Cow::Borrowed(&word[..len]),
+            Cow::Owned(mut word) => {
+                word.truncate(len);
+                Cow::Owned(word)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "stemming"))]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_snowball_english() {
+        let stemmer = SnowballStemmer::new(Language::English);
+        for word in ["test", "tests", "testing", "tested"] {
+            assert_eq!("test", stemmer.stem(Cow::Borrowed(word)));
+        }
+    }
+
+    #[test]
+    fn test_snowball_borrows() {
+        let stemmer = SnowballStemmer::new(Language::English);
+        assert!(matches!(
+            stemmer.stem(Cow::Borrowed("testing")),
+            Cow::Borrowed("test")
+        ));
+    }
+
+    #[test]
+    fn test_snowball_owned() {
+        let stemmer = SnowballStemmer::new(Language::English);
+        assert_eq!("test", stemmer.stem(Cow::Owned("testing".to_string())));
+        assert_eq!("test", stemmer.stem(Cow::Owned("test".to_string())));
+    }
+}
@@ -0,0 +1,366 @@
+//! Stop words: very common function words that carry little
+//! meaning for text analysis, such as "the", "is" and "a".
+//!
+//! A [StopWords] set can be attached to a [Bbow](crate::Bbow)
+//! with [Bbow::with_stop_words](crate::Bbow::with_stop_words),
+//! so that stop words are dropped as text is added, or applied
+//! afterward with
+//! [Bbow::remove_stop_words](crate::Bbow::remove_stop_words).
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Normalizer;
+
+/// A language with a built-in stop word list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Dutch,
+    English,
+    French,
+    German,
+    Italian,
+    Portuguese,
+    Spanish,
+}
+
+impl Language {
+    /// The built-in stop words for this language. Words are
+    /// lowercase, and words with internal punctuation (which
+    /// can never be in a BBOW) are omitted.
+    pub fn stop_words(self) -> &'static [&'static str] {
+        match self {
+            Language::Dutch => DUTCH,
+            Language::English => ENGLISH,
+            Language::French => FRENCH,
+            Language::German => GERMAN,
+            Language::Italian => ITALIAN,
+            Language::Portuguese => PORTUGUESE,
+            Language::Spanish => SPANISH,
+        }
+    }
+}
+
+/// A set of words to leave out of a BBOW.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StopWords(BTreeSet<String>);
+
+impl StopWords {
+    /// Make a new empty stop word set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a stop word set from the built-in list for
+    /// `language`.
+    pub fn language(language: Language) -> Self {
+        language.stop_words().iter().copied().collect()
+    }
+
+    /// Add the built-in list for `language` to this set.
+    ///
+    /// This is a builder method, so that lists can be
+    /// combined: `StopWords::language(English).with_language(French)`.
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.extend(language.stop_words().iter().copied());
+        self
+    }
+
+    /// Read a stop word list from the file at `path`.
+    ///
+    /// The file holds whitespace-separated words, typically
+    /// one per line. Lines starting with `#` are comments.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Parse a stop word list in the format read by
+    /// [StopWords::from_file].
+    pub fn parse(text: &str) -> Self {
+        text.lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    /// Is `word` in this set?
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(word)
+    }
+
+    /// Number of words in this set.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Is this set empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The words of this set, in sorted order.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|w| w.as_str())
+    }
+
+    /// Run every word of this set through `normalizer`, so
+    /// that the set matches keys normalized the same way.
+    pub(crate) fn normalized(&self, normalizer: &Normalizer) -> Self {
+        self.words()
+            .map(|w| normalizer.normalize(w.into()).into_owned())
+            .collect()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for StopWords {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        StopWords(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<S: Into<String>> Extend<S> for StopWords {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+const DUTCH: &[&str] = &[
+    "aan", "al", "alles", "als", "altijd", "andere", "ben", "bij", "daar", "dan", "dat", "de",
+    "der", "deze", "die", "dit", "doch", "doen", "door", "dus", "een", "eens", "en", "er", "ge",
+    "geen", "geweest", "haar", "had", "heb", "hebben", "heeft", "hem", "het", "hier", "hij", "hoe",
+    "hun", "iemand", "iets", "ik", "in", "is", "ja", "je", "kan", "kon", "kunnen", "maar", "me",
+    "meer", "men", "met", "mij", "mijn", "moet", "na", "naar", "niet", "niets", "nog", "nu", "of",
+    "om", "omdat", "onder", "ons", "ook", "op", "over", "reeds", "te", "tegen", "toch", "toen",
+    "tot", "u", "uit", "uw", "van", "veel", "voor", "want", "waren", "was", "wat", "werd", "wezen",
+    "wie", "wil", "worden", "wordt", "zal", "ze", "zelf", "zich", "zij", "zijn", "zo", "zonder",
+    "zou",
+];
+
+const ENGLISH: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "itself",
+    "just",
+    "me",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+const FRENCH: &[&str] = &[
+    "à", "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "elles", "en", "et",
+    "été", "être", "eu", "il", "ils", "je", "la", "le", "les", "leur", "leurs", "lui", "ma",
+    "mais", "me", "même", "mes", "moi", "mon", "ne", "nos", "notre", "nous", "on", "ou", "où",
+    "par", "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sont", "sur", "ta", "te",
+    "tes", "toi", "ton", "tu", "un", "une", "vos", "votre", "vous", "y",
+];
+
+const GERMAN: &[&str] = &[
+    "aber", "alle", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "bist",
+    "da", "damit", "dann", "das", "dass", "dein", "dem", "den", "der", "des", "dich", "die", "dir",
+    "doch", "du", "durch", "ein", "eine", "einem", "einen", "einer", "eines", "er", "es", "für",
+    "hat", "hatte", "ich", "ihm", "ihn", "ihr", "im", "in", "ist", "ja", "kann", "kein", "man",
+    "mein", "mich", "mir", "mit", "nach", "nicht", "noch", "nun", "nur", "ob", "oder", "ohne",
+    "sehr", "sein", "sich", "sie", "sind", "so", "über", "um", "und", "uns", "unter", "vom", "von",
+    "vor", "war", "was", "weil", "wenn", "wie", "wir", "wird", "zu", "zum", "zur",
+];
+
+const ITALIAN: &[&str] = &[
+    "a", "ad", "agli", "al", "alla", "alle", "anche", "che", "chi", "ci", "come", "con", "da",
+    "dal", "dalla", "degli", "dei", "del", "della", "delle", "di", "e", "è", "ed", "gli", "ha",
+    "hanno", "ho", "i", "il", "in", "io", "la", "le", "lei", "lo", "loro", "lui", "ma", "mi",
+    "mio", "ne", "negli", "nel", "nella", "noi", "non", "o", "per", "più", "quale", "quello",
+    "questo", "se", "si", "sono", "su", "sua", "suo", "sul", "sulla", "ti", "tra", "tu", "un",
+    "una", "uno", "voi",
+];
+
+const PORTUGUESE: &[&str] = &[
+    "a", "ao", "aos", "as", "até", "com", "como", "da", "das", "de", "dela", "dele", "do", "dos",
+    "e", "é", "ela", "elas", "ele", "eles", "em", "entre", "era", "essa", "esse", "esta", "este",
+    "eu", "foi", "há", "isso", "isto", "já", "lhe", "mais", "mas", "me", "mesmo", "meu", "minha",
+    "muito", "na", "não", "nas", "nem", "no", "nos", "nós", "o", "os", "ou", "para", "pela",
+    "pelo", "por", "qual", "quando", "que", "quem", "se", "seu", "sua", "são", "só", "também",
+    "te", "tem", "um", "uma", "você",
+];
+
+const SPANISH: &[&str] = &[
+    "a", "al", "algo", "ante", "como", "con", "contra", "cuando", "de", "del", "desde", "donde",
+    "durante", "e", "el", "él", "ella", "ellas", "ellos", "en", "entre", "era", "es", "esa", "ese",
+    "eso", "esta", "está", "este", "esto", "fue", "ha", "hay", "la", "las", "le", "les", "lo",
+    "los", "más", "me", "mi", "muy", "nada", "ni", "no", "nos", "o", "otro", "para", "pero", "por",
+    "porque", "que", "qué", "se", "sea", "ser", "si", "sí", "sin", "sobre", "su", "sus", "también",
+    "te", "tu", "un", "una", "uno", "y", "ya", "yo",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        let stop_words = StopWords::language(Language::English);
+        assert!(stop_words.contains("the"));
+        assert!(!stop_words.contains("test"));
+    }
+
+    #[test]
+    fn test_with_language() {
+        let stop_words = StopWords::language(Language::English).with_language(Language::French);
+        assert!(stop_words.contains("the"));
+        assert!(stop_words.contains("avec"));
+    }
+
+    #[test]
+    fn test_builtin_lists_are_lowercase_and_unique() {
+        for language in [
+            Language::Dutch,
+            Language::English,
+            Language::French,
+            Language::German,
+            Language::Italian,
+            Language::Portuguese,
+            Language::Spanish,
+        ] {
+            let words = language.stop_words();
+            assert!(words.iter().all(|w| w.chars().all(char::is_lowercase)));
+            assert_eq!(words.len(), StopWords::language(language).len());
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let stop_words = StopWords::parse("# my list\nfoo\nbar baz\n  # more\n\nquux\n");
+        let words: Vec<_> = stop_words.words().collect();
+        assert_eq!(vec!["bar", "baz", "foo", "quux"], words);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("bbow-stop-{}.txt", std::process::id()));
+        fs::write(&path, "foo\nbar\n").unwrap();
+        let stop_words = StopWords::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(2, stop_words.unwrap().len());
+    }
+}
@@ -0,0 +1,38 @@
+//! Helpers shared by the unit tests of several modules.
+
+use crate::{Bbow, Corpus, InvertedIndex};
+
+/// Are `expected` and `actual` equal but for rounding?
+pub(crate) fn close(expected: f64, actual: f64) -> bool {
+    (expected - actual).abs() < 1e-9
+}
+
+/// The bag of the words of `text`, with default settings.
+pub(crate) fn bag(text: &str) -> Bbow<'_> {
+    Bbow::new().extend_from_text(text)
+}
+
+/// The words of `bag` with their counts, in sorted order.
+pub(crate) fn pairs(bag: &Bbow<'_>) -> Vec<(String, usize)> {
+    bag.iter().map(|(w, c)| (w.to_string(), c)).collect()
+}
+
+/// A corpus of the given `(id, text)` documents, with
+/// default settings.
+pub(crate) fn corpus(documents: &[(&str, &'static str)]) -> Corpus<'static> {
+    let mut corpus = Corpus::new();
+    for &(id, text) in documents {
+        corpus.add_text(id, text);
+    }
+    corpus
+}
+
+/// An index of the given `(id, text)` documents, with
+/// default settings.
+pub(crate) fn index(documents: &[(&str, &'static str)]) -> InvertedIndex<'static> {
+    let mut index = InvertedIndex::new();
+    for &(id, text) in documents {
+        index.add_text(id, text);
+    }
+    index
+}
@@ -0,0 +1,247 @@
+//! Splitting text into candidate words.
+//!
+//! A [Tokenizer] decides where the words of a text begin and
+//! end. The tokens it yields are what a [Bbow](crate::Bbow)
+//! counts, after normalization.
+
+use std::borrow::Cow;
+
+use unicode_general_category::{get_general_category, GeneralCategory};
+
+/// A rule for splitting text into words.
+///
+/// Tokens are `Cow`s so that a tokenizer can hand back
+/// slices of the original text when no rewriting is needed,
+/// keeping the bag zero-copy.
+pub trait Tokenizer {
+    /// Split `text` into its sequence of words.
+    fn tokens<'s, 'a: 's>(&'s self, text: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's>;
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn tokens<'s, 'a: 's>(&'s self, text: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+        (**self).tokens(text)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Box<T> {
+    fn tokens<'s, 'a: 's>(&'s self, text: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+        (**self).tokens(text)
+    }
+}
+
+/// Is `c` in one of the Unicode punctuation categories
+/// (Pc, Pd, Ps, Pe, Pi, Pf, Po)?
+fn is_punctuation(c: char) -> bool {
+    use GeneralCategory::*;
+    matches!(
+        get_general_category(c),
+        ConnectorPunctuation
+            | DashPunctuation
+            | OpenPunctuation
+            | ClosePunctuation
+            | InitialPunctuation
+            | FinalPunctuation
+            | OtherPunctuation
+    )
+}
+
+/// Is `c` in one of the Unicode symbol categories
+/// (Sm, Sc, Sk, So)?
+fn is_symbol(c: char) -> bool {
+    use GeneralCategory::*;
+    matches!(
+        get_general_category(c),
+        MathSymbol | CurrencySymbol | ModifierSymbol | OtherSymbol
+    )
+}
+
+/// The default tokenizer, as described in the crate
+/// documentation.
+///
+/// Words are separated by whitespace. Leading and trailing
+/// Unicode punctuation is removed, and what remains must be
+/// one or more letters: anything else is dropped.
+///
+/// Symbols such as `$` or `©` are left in place by default,
+/// so a part like `$5` or `word©` is not a word. Use
+/// [WordTokenizer::trim_symbols] or [WordTokenizer::trim_chars]
+/// to strip them as well.
+#[derive(Debug, Default, Clone)]
+pub struct WordTokenizer {
+    trim_symbols: bool,
+    extra: Vec<char>,
+}
+
+impl WordTokenizer {
+    /// Make a tokenizer that trims punctuation only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also trim characters in the Unicode symbol categories
+    /// (Sm, Sc, Sk, So).
+    pub fn trim_symbols(mut self, trim: bool) -> Self {
+        self.trim_symbols = trim;
+        self
+    }
+
+    /// Also trim each of the given characters.
+    pub fn trim_chars<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.extra.extend(chars);
+        self
+    }
+
+    fn is_trimmed(&self, c: char) -> bool {
+        is_punctuation(c) || (self.trim_symbols && is_symbol(c)) || self.extra.contains(&c)
+    }
+}
+
+impl Tokenizer for WordTokenizer {
+    fn tokens<'s, 'a: 's>(&'s self, text: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+        Box::new(
+            text.split_whitespace()
+                .map(|part| part.trim_matches(|c| self.is_trimmed(c)))
+                .filter(|part| is_word(part))
+                .map(Cow::Borrowed),
+        )
+    }
+}
+
+/// A tokenizer that finds words using the Unicode word
+/// boundary rules of [UAX #29](https://www.unicode.org/reports/tr29/),
+/// rather than by splitting on whitespace.
+///
+/// This copes with text like `word—word` or `foo/bar`, and
+/// with scripts written without spaces between words. Each
+/// segment must still be a span of letters to count as a
+/// word, so `ain't` and `42` are dropped just as with
+/// [WordTokenizer].
+///
+/// UAX #29 has no dictionary: ideographs, kana and Thai
+/// letters come out one character at a time, each with any
+/// marks that follow it.
+///
+/// Requires the `segmentation` feature.
+#[cfg(feature = "segmentation")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SegmentTokenizer;
+
+#[cfg(feature = "segmentation")]
+impl Tokenizer for SegmentTokenizer {
+    fn tokens<'s, 'a: 's>(&'s self, text: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 's> {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        Box::new(
+            text.split_word_bounds()
+                .filter(|segment| is_word(segment))
+                .map(Cow::Borrowed),
+        )
+    }
+}
+
+/// A word is a nonempty span of letters. Combining marks
+/// may follow a letter, so that decomposed text such as
+/// `unti\u{308}l` is accepted along with `untïl`.
+pub(crate) fn is_word(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_alphabetic) && chars.all(|c| c.is_alphabetic() || is_mark(c))
+}
+
+/// Is `c` in one of the Unicode mark categories (Mn, Mc, Me)?
+fn is_mark(c: char) -> bool {
+    use GeneralCategory::*;
+    matches!(
+        get_general_category(c),
+        NonspacingMark | SpacingMark | EnclosingMark
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<Cow<'_, str>> {
+        WordTokenizer::new().tokens(text).collect()
+    }
+
+    #[test]
+    fn test_word_tokenizer_doc_example() {
+        let text = "It ain't over untïl it ain't, over.";
+        assert_eq!(vec!["It", "over", "untïl", "it", "over"], words(text));
+    }
+
+    #[test]
+    fn test_word_tokenizer_borrows() {
+        assert!(words("Stop! stop?")
+            .iter()
+            .all(|w| matches!(w, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn test_word_tokenizer_rejects_non_words() {
+        assert!(words("b-banana 42 ... x1").is_empty());
+    }
+
+    #[test]
+    fn test_word_tokenizer_combining_marks() {
+        assert_eq!(vec!["unti\u{308}l"], words("unti\u{308}l"));
+        assert!(words("\u{308}until").is_empty());
+    }
+
+    #[test]
+    fn test_word_tokenizer_typographic_quotes() {
+        let text = "“Hello,” she said — ‘quietly’ (again) «bonjour» ¿qué?";
+        assert_eq!(
+            vec!["Hello", "she", "said", "quietly", "again", "bonjour", "qué"],
+            words(text)
+        );
+    }
+
+    #[test]
+    fn test_word_tokenizer_keeps_symbols_by_default() {
+        assert!(words("$dollars cents¢").is_empty());
+    }
+
+    #[test]
+    fn test_word_tokenizer_trim_symbols() {
+        let tokenizer = WordTokenizer::new().trim_symbols(true);
+        let words: Vec<_> = tokenizer.tokens("$dollars cents¢").collect();
+        assert_eq!(vec!["dollars", "cents"], words);
+    }
+
+    #[test]
+    fn test_word_tokenizer_trim_chars() {
+        let tokenizer = WordTokenizer::new().trim_chars(['$', '+']);
+        let words: Vec<_> = tokenizer.tokens("$dollars +plus ©copy").collect();
+        assert_eq!(vec!["dollars", "plus"], words);
+    }
+
+    #[cfg(feature = "segmentation")]
+    #[test]
+    fn test_segment_tokenizer() {
+        let words: Vec<_> = SegmentTokenizer
+            .tokens("word—word foo/bar “It ain't over,” 42")
+            .collect();
+        assert_eq!(vec!["word", "word", "foo", "bar", "It", "over"], words);
+    }
+
+    #[cfg(feature = "segmentation")]
+    #[test]
+    fn test_segment_tokenizer_without_spaces() {
+        let words: Vec<_> = SegmentTokenizer.tokens("東京へ行きます。").collect();
+        assert_eq!(vec!["東", "京", "へ", "行", "き", "ま", "す"], words);
+    }
+
+    #[test]
+    fn test_word_tokenizer_thai() {
+        assert_eq!(vec!["สวัสดีครับ", "ขอบคุณ"], words("สวัสดีครับ ขอบคุณ"));
+    }
+
+    #[cfg(feature = "segmentation")]
+    #[test]
+    fn test_segment_tokenizer_thai() {
+        let words: Vec<_> = SegmentTokenizer.tokens("สวัสดีครับ").collect();
+        assert_eq!(vec!["ส", "วั", "ส", "ดี", "ค", "รั", "บ"], words);
+    }
+}
@@ -0,0 +1,223 @@
+//! Compact bags that refer to words by number.
+//!
+//! Each [Bbow] stores its own copy of every word it counts
+//! (unless the word is borrowed from a text that is still in
+//! memory). When many bags are kept, most of that storage is
+//! the same words over and over. A [Vocabulary] stores each
+//! word once and numbers it, and an [IdBbow] stores only the
+//! numbers and counts of its words: a few bytes per word
+//! however long the word is.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::Bbow;
+
+/// A set of words, each with a `u32` id. Ids are given out
+/// in order from 0 as words are first seen, and never
+/// change.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    words: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, u32>,
+}
+
+impl Vocabulary {
+    /// Make a new empty vocabulary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id of `word`, adding it to the vocabulary if it is
+    /// not there yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vocabulary already holds `u32::MAX + 1`
+    /// words.
+    pub fn intern(&mut self, word: &str) -> u32 {
+        if let Some(&id) = self.ids.get(word) {
+            return id;
+        }
+        let id = u32::try_from(self.words.len()).expect("vocabulary is full");
+        let word: Arc<str> = Arc::from(word);
+        self.words.push(Arc::clone(&word));
+        self.ids.insert(word, id);
+        id
+    }
+
+    /// The id of `word`, if it is in the vocabulary.
+    pub fn id(&self, word: &str) -> Option<u32> {
+        self.ids.get(word).copied()
+    }
+
+    /// The word with the given `id`, if there is one.
+    pub fn word(&self, id: u32) -> Option<&str> {
+        self.words.get(id as usize).map(|w| w.as_ref())
+    }
+
+    /// The words of the vocabulary with their ids, in id
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        (0..).zip(self.words.iter().map(|w| w.as_ref()))
+    }
+
+    /// Number of words in the vocabulary.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Is the vocabulary empty?
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Intern each word, in order.
+impl<'w> Extend<&'w str> for Vocabulary {
+    fn extend<I: IntoIterator<Item = &'w str>>(&mut self, words: I) {
+        for word in words {
+            self.intern(word);
+        }
+    }
+}
+
+impl<'w> FromIterator<&'w str> for Vocabulary {
+    fn from_iter<I: IntoIterator<Item = &'w str>>(words: I) -> Self {
+        let mut vocabulary = Vocabulary::new();
+        vocabulary.extend(words);
+        vocabulary
+    }
+}
+
+/// A bag of words keyed by their ids in a [Vocabulary].
+///
+/// An [IdBbow] does not record which vocabulary its ids
+/// belong to: it must always be used with the vocabulary it
+/// was made with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdBbow {
+    /// Word ids and their counts, in id order.
+    counts: Vec<(u32, usize)>,
+}
+
+impl IdBbow {
+    /// Convert `bag` to ids, adding its words to `vocabulary`
+    /// as needed.
+    pub fn from_bbow(bag: &Bbow<'_>, vocabulary: &mut Vocabulary) -> Self {
+        let mut counts: Vec<_> = bag
+            .iter()
+            .map(|(word, count)| (vocabulary.intern(word), count))
+            .collect();
+        counts.sort_unstable();
+        IdBbow { counts }
+    }
+
+    /// Convert back to a [Bbow] whose words are borrowed from
+    /// `vocabulary`. The bag has default settings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an id of this bag is not in `vocabulary`.
+    pub fn to_bbow<'v>(&self, vocabulary: &'v Vocabulary) -> Bbow<'v> {
+        self.counts
+            .iter()
+            .map(|&(id, count)| {
+                let word = vocabulary.word(id).expect("id not in vocabulary");
+                (word, count)
+            })
+            .collect()
+    }
+
+    /// The count of the word with the given `id`, or 0 if it
+    /// is not in this bag.
+    pub fn get(&self, id: u32) -> usize {
+        self.counts
+            .binary_search_by_key(&id, |&(id, _)| id)
+            .map_or(0, |i| self.counts[i].1)
+    }
+
+    /// The ids of the words in this bag with their counts,
+    /// in id order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, usize)> + '_ {
+        self.counts.iter().copied()
+    }
+
+    /// Count the overall number of words in this bag:
+    /// multiple occurrences are considered separate.
+    pub fn count(&self) -> usize {
+        self.counts.iter().map(|&(_, count)| count).sum()
+    }
+
+    /// Count the number of unique words in this bag.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Is this bag empty?
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_intern() {
+        let mut vocabulary = Vocabulary::new();
+        assert_eq!(0, vocabulary.intern("stop"));
+        assert_eq!(1, vocabulary.intern("this"));
+        assert_eq!(0, vocabulary.intern("stop"));
+        assert_eq!(Some(1), vocabulary.id("this"));
+        assert_eq!(None, vocabulary.id("test"));
+        assert_eq!(Some("stop"), vocabulary.word(0));
+        assert_eq!(None, vocabulary.word(2));
+        assert_eq!(
+            vec![(0, "stop"), (1, "this")],
+            vocabulary.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(2, vocabulary.len());
+
+        vocabulary.extend(["test", "stop"]);
+        assert_eq!(Some(2), vocabulary.id("test"));
+        let collected: Vocabulary = ["b", "a", "b"].into_iter().collect();
+        assert_eq!(2, collected.len());
+    }
+
+    #[test]
+    fn test_shared_vocabulary() {
+        let mut vocabulary = Vocabulary::new();
+        let a = IdBbow::from_bbow(
+            &Bbow::new().extend_from_text("Can't stop this! Stop!"),
+            &mut vocabulary,
+        );
+        let b = IdBbow::from_bbow(
+            &Bbow::new().extend_from_text("stop testing this"),
+            &mut vocabulary,
+        );
+        assert_eq!(3, vocabulary.len());
+        let stop = vocabulary.id("stop").unwrap();
+        assert_eq!(2, a.get(stop));
+        assert_eq!(1, b.get(stop));
+        assert_eq!(0, a.get(vocabulary.id("testing").unwrap()));
+        assert_eq!(3, a.count());
+        assert_eq!(2, a.len());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "It ain't over untïl it ain't, over.";
+        let bag = Bbow::new().extend_from_text(text);
+        let mut vocabulary = Vocabulary::new();
+        vocabulary.intern("zebra");
+        let ids = IdBbow::from_bbow(&bag, &mut vocabulary);
+        let back = ids.to_bbow(&vocabulary);
+        assert!(back.iter().eq(bag.iter()));
+        assert!(back
+            .into_iter()
+            .all(|(word, _)| matches!(word, Cow::Borrowed(_))));
+    }
+}